@@ -1,5 +1,7 @@
 use clap::{Parser, Subcommand};
 
+use crate::commands::similar::HashAlgorithm;
+
 #[derive(Parser)]
 #[command(
     name = "fiq",
@@ -27,6 +29,51 @@ pub enum Commands {
         #[arg(long, default_value = "10")]
         top: usize,
 
+        /// Break down by content-detected type (reads file headers)
+        #[arg(long)]
+        by_type: bool,
+
+        /// Count code, comment and blank lines per programming language
+        #[arg(long)]
+        code: bool,
+
+        /// Break down by file owner or group: owner, group
+        #[arg(long)]
+        by: Option<String>,
+
+        /// List world-writable files, setuid/setgid executables and files
+        /// not owned by the current user
+        #[arg(long)]
+        audit: bool,
+
+        /// Show cumulative size per directory and the largest directories
+        #[arg(long)]
+        tree: bool,
+
+        /// Directory levels to show with --tree
+        #[arg(long, default_value = "2")]
+        depth: usize,
+
+        /// Count space allocated on disk instead of apparent size
+        #[arg(long, overrides_with = "apparent_size")]
+        disk_usage: bool,
+
+        /// Count apparent size (file length); the default
+        #[arg(long, overrides_with = "disk_usage")]
+        apparent_size: bool,
+
+        /// Show size and age histograms, overall and per extension
+        #[arg(long)]
+        histograms: bool,
+
+        /// Save a snapshot of per-directory and per-extension totals
+        #[arg(long)]
+        save_snapshot: bool,
+
+        /// Report changes since a snapshot: its id, its file, or "last"
+        #[arg(long, value_name = "SNAPSHOT")]
+        compare: Option<String>,
+
         /// Scan recursively
         #[arg(long, short, default_value = "true")]
         recursive: bool,
@@ -42,6 +89,121 @@ pub enum Commands {
         #[arg(long, default_value = "1")]
         min_size: u64,
 
+        /// Count wasted space as allocated disk space instead of apparent size
+        #[arg(long, overrides_with = "apparent_size")]
+        disk_usage: bool,
+
+        /// Count apparent size (file length); the default
+        #[arg(long, overrides_with = "disk_usage")]
+        apparent_size: bool,
+
+        /// Scan recursively
+        #[arg(long, short, default_value = "true")]
+        recursive: bool,
+    },
+
+    /// Rank old, large files by size × age to find data worth archiving
+    Cold {
+        /// Directory to scan
+        #[arg(default_value = ".")]
+        directory: String,
+
+        /// Files last used before this (e.g. "180d", "2024-01-01")
+        #[arg(long, default_value = "180d")]
+        older: String,
+
+        /// Minimum file size (e.g. "100MB", "1GB")
+        #[arg(long, default_value = "100MB")]
+        min_size: String,
+
+        /// Number of files to list
+        #[arg(long, default_value = "20")]
+        top: usize,
+
+        /// Age files by last access (atime) as well as last modification
+        #[arg(long)]
+        by_access: bool,
+
+        /// Scan recursively
+        #[arg(long, short, default_value = "true")]
+        recursive: bool,
+    },
+
+    /// Create or verify checksum manifests (b3sum/sha256sum compatible)
+    Checksum {
+        #[command(subcommand)]
+        action: ChecksumCommands,
+    },
+
+    /// Compare two directory trees by content
+    Diff {
+        /// Left directory (e.g. the backup)
+        left: String,
+
+        /// Right directory (e.g. the live copy)
+        right: String,
+
+        /// Confirm same-size files with blake3 instead of trusting mtime
+        #[arg(long)]
+        verify: bool,
+
+        /// Print the result as JSON
+        #[arg(long)]
+        json: bool,
+
+        /// Scan recursively
+        #[arg(long, short, default_value = "true")]
+        recursive: bool,
+    },
+
+    /// Find every copy of a file (or blake3 digest) across directories
+    FindCopies {
+        /// File to look for, or its blake3 hex digest
+        target: String,
+
+        /// Directories to search
+        #[arg(required = true)]
+        directories: Vec<String>,
+
+        /// Ignore and don't update the persisted hash cache
+        #[arg(long)]
+        no_cache: bool,
+
+        /// Scan recursively
+        #[arg(long, short, default_value = "true")]
+        recursive: bool,
+    },
+
+    /// Find near-duplicate files by perceptual similarity
+    Similar {
+        /// Directory to scan
+        #[arg(default_value = ".")]
+        directory: String,
+
+        /// Compare images (JPEG, PNG, WebP, GIF) by perceptual hash
+        #[arg(long)]
+        images: bool,
+
+        /// Perceptual hash algorithm
+        #[arg(long, value_enum, default_value = "dhash")]
+        algorithm: HashAlgorithm,
+
+        /// Maximum Hamming distance (out of 64 bits) for images to be grouped
+        #[arg(long, default_value = "10")]
+        threshold: u32,
+
+        /// Compare text files by MinHash over word shingles
+        #[arg(long)]
+        text: bool,
+
+        /// Minimum estimated Jaccard similarity (0.0-1.0) for text files to be grouped
+        #[arg(long, default_value = "0.8")]
+        min_similarity: f64,
+
+        /// Minimum file size to consider (bytes)
+        #[arg(long, default_value = "1")]
+        min_size: u64,
+
         /// Scan recursively
         #[arg(long, short, default_value = "true")]
         recursive: bool,
@@ -77,28 +239,45 @@ pub enum Commands {
         #[arg(long)]
         older: Option<String>,
 
+        /// Use photo/video capture time (EXIF, MP4/MOV) for date filters
+        #[arg(long)]
+        media_date: bool,
+
         /// Scan recursively
         #[arg(long, short, default_value = "true")]
         recursive: bool,
     },
 
-    /// Organize files into folders by type, date, or size
+    /// Organize files into folders by type, date, size, or custom rules
     Organize {
         /// Directory to organize
         directory: String,
 
-        /// Organization strategy
+        /// Organization strategy: type, mime, date, size, rules, template
         #[arg(long, default_value = "type")]
         by: String,
 
+        /// Rules file for --by rules (default: ~/.config/fiq/rules.toml)
+        #[arg(long)]
+        rules: Option<String>,
+
+        /// Destination template for --by template, e.g. "{category}/{year}/{stem}.{ext}"
+        #[arg(long)]
+        template: Option<String>,
+
         /// Preview changes without moving files
         #[arg(long)]
         dry_run: bool,
 
-        /// How to handle conflicts: skip, rename, overwrite
+        /// How to handle conflicts: skip, rename, overwrite, dedupe (drop
+        /// byte-identical files, rename others), newer-wins (keep the newer mtime)
         #[arg(long, default_value = "rename")]
         mode: String,
 
+        /// How to place files: move, copy, hardlink, symlink
+        #[arg(long, default_value = "move")]
+        action: String,
+
         /// Process subdirectories
         #[arg(long, short, default_value = "true")]
         recursive: bool,
@@ -106,5 +285,142 @@ pub enum Commands {
         /// Output directory (default: organize in-place)
         #[arg(long)]
         output: Option<String>,
+
+        /// Record blake3 hashes in the undo journal so undo can detect edits
+        #[arg(long)]
+        hash: bool,
+
+        /// Date photos and videos by embedded capture time (EXIF, MP4/MOV)
+        /// instead of mtime, falling back to mtime
+        #[arg(long)]
+        media_date: bool,
+
+        /// Remove directories left empty after moving files out of them
+        #[arg(long)]
+        prune_empty: bool,
+
+        /// Skip files that fail instead of aborting the run and rolling back
+        #[arg(long)]
+        keep_going: bool,
+
+        /// Keep running and organize new files as they arrive (stop with Ctrl-C)
+        #[arg(long)]
+        watch: bool,
+
+        /// With --watch, seconds without changes before new files are organized
+        #[arg(long, default_value = "5")]
+        settle: u64,
+    },
+
+    /// Rename files by regex and name template (preview unless --apply)
+    Rename {
+        /// Directory with the files to rename
+        directory: String,
+
+        /// Regex matched against file names; only matching files are renamed
+        #[arg(long = "match")]
+        pattern: String,
+
+        /// New name: {1}, {2}... capture groups, {n} or {n:3} counter, {stem},
+        /// {ext}, {date}, {year}...; add :upper, :lower or :title to any placeholder
+        #[arg(long)]
+        to: String,
+
+        /// Rename the files instead of only previewing
+        #[arg(long)]
+        apply: bool,
+
+        /// Use photo/video capture time (EXIF, MP4/MOV) for date placeholders
+        #[arg(long)]
+        media_date: bool,
+
+        /// Rename in subdirectories too
+        #[arg(long, short, default_value = "true")]
+        recursive: bool,
+    },
+
+    /// Find empty files and directories, broken symlinks and junk files
+    Clean {
+        /// Directory to scan
+        #[arg(default_value = ".")]
+        directory: String,
+
+        /// Only report these kinds: empty-files, empty-dirs,
+        /// broken-symlinks, junk (repeatable; default: all)
+        #[arg(long = "kind", value_name = "KIND")]
+        kinds: Vec<String>,
+
+        /// Extra junk name pattern, e.g. "*.tmp" (repeatable)
+        #[arg(long, value_name = "GLOB")]
+        junk: Vec<String>,
+
+        /// Only treat --junk patterns as junk, not the built-in list
+        #[arg(long)]
+        no_default_junk: bool,
+
+        /// Move everything reported to fiq's trash (undo with `fiq undo`)
+        #[arg(long)]
+        trash: bool,
+
+        /// Scan recursively
+        #[arg(long, short, default_value = "true")]
+        recursive: bool,
+    },
+
+    /// Reverse an organize, rename or clean run using its journal
+    Undo {
+        /// Run id to undo (default: the most recent run)
+        run_id: Option<String>,
+
+        /// Preview what would be restored without moving files
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Restore files even if they changed since the move
+        #[arg(long)]
+        force: bool,
+
+        /// List runs that can be undone
+        #[arg(long)]
+        list: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ChecksumCommands {
+    /// Hash every file in a directory and write a manifest
+    Create {
+        /// Directory to hash
+        directory: String,
+
+        /// Manifest file to write (default: print to stdout)
+        #[arg(long, short)]
+        output: Option<String>,
+
+        /// Hash algorithm: blake3, sha256
+        #[arg(long, default_value = "blake3")]
+        algo: String,
+
+        /// Scan recursively
+        #[arg(long, short, default_value = "true")]
+        recursive: bool,
+    },
+
+    /// Check files against a manifest, reporting changed, missing and new files
+    Verify {
+        /// Manifest file to check
+        manifest: String,
+
+        /// Directory the manifest paths are relative to (default: the manifest's directory)
+        #[arg(long)]
+        root: Option<String>,
+
+        /// Hash algorithm for untagged lines (default: guessed from the manifest name)
+        #[arg(long)]
+        algo: Option<String>,
+
+        /// Scan recursively when looking for new files
+        #[arg(long, short, default_value = "true")]
+        recursive: bool,
     },
 }
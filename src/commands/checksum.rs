@@ -0,0 +1,397 @@
+use std::collections::HashSet;
+use std::fs::File;
+use std::path::{Component, Path, PathBuf};
+
+use memmap2::Mmap;
+use rayon::prelude::*;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::commands::duplicates::hash_file;
+use crate::scanner::scan_directory;
+
+const MMAP_THRESHOLD: u64 = 128 * 1024;
+
+#[derive(Debug, Serialize)]
+pub struct ChecksumCreateResult {
+    pub algorithm: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub manifest: Option<String>,
+    pub files_hashed: usize,
+    pub total_size: u64,
+    #[serde(skip)]
+    pub manifest_text: String,
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ChecksumVerifyResult {
+    pub manifest: String,
+    pub algorithm: String,
+    pub ok: usize,
+    pub changed: Vec<String>,
+    pub missing: Vec<String>,
+    pub new: Vec<String>,
+    pub errors: Vec<String>,
+}
+
+impl ChecksumVerifyResult {
+    /// True when every listed file is present and unchanged.
+    pub fn passed(&self) -> bool {
+        self.changed.is_empty() && self.missing.is_empty() && self.errors.is_empty()
+    }
+}
+
+/// Hash a file with the named algorithm ("blake3" or "sha256").
+fn digest_file(path: &Path, size: u64, algorithm: &str) -> Option<String> {
+    if algorithm == "blake3" {
+        return hash_file(path, size);
+    }
+
+    let digest = if size >= MMAP_THRESHOLD {
+        let file = File::open(path).ok()?;
+        let mmap = unsafe { Mmap::map(&file).ok()? };
+        Sha256::digest(&mmap[..])
+    } else {
+        Sha256::digest(std::fs::read(path).ok()?)
+    };
+    Some(digest.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// Normalize user-facing algorithm names. Returns None for unsupported ones.
+fn normalize_algorithm(name: &str) -> Option<&'static str> {
+    match name.to_lowercase().as_str() {
+        "blake3" | "b3" | "b3sum" => Some("blake3"),
+        "sha256" | "sha-256" | "sha256sum" => Some("sha256"),
+        _ => None,
+    }
+}
+
+/// Guess the algorithm from a manifest file name (`*.sha256`, `SHA256SUMS`),
+/// defaulting to blake3. Both formats use 64 hex digits, so the name is the
+/// only reliable hint for untagged manifests.
+fn algorithm_from_name(manifest: &Path) -> &'static str {
+    let name = manifest
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+    if name.contains("sha256") {
+        "sha256"
+    } else {
+        "blake3"
+    }
+}
+
+/// Format one manifest line in coreutils style. Paths containing a backslash
+/// or newline are escaped and the line is prefixed with `\`, as b3sum and
+/// sha256sum do.
+fn format_line(hash: &str, rel: &str) -> String {
+    if rel.contains('\\') || rel.contains('\n') {
+        let escaped = rel.replace('\\', "\\\\").replace('\n', "\\n");
+        format!("\\{}  {}\n", hash, escaped)
+    } else {
+        format!("{}  {}\n", hash, rel)
+    }
+}
+
+/// Parse one manifest line. Accepts the GNU format (`hash  path`, `hash *path`)
+/// and the BSD tag format (`SHA256 (path) = hash`). Returns
+/// (algorithm if tagged, hash, path).
+fn parse_line(line: &str) -> Option<(Option<&'static str>, String, String)> {
+    if let Some((tag, rest)) = line.split_once(" (")
+        && let Some((path, hash)) = rest.rsplit_once(") = ")
+    {
+        return Some((
+            normalize_algorithm(tag),
+            hash.to_lowercase(),
+            path.to_string(),
+        ));
+    }
+
+    let (escaped, line) = match line.strip_prefix('\\') {
+        Some(rest) => (true, rest),
+        None => (false, line),
+    };
+    let (hash, path) = line.split_once(' ')?;
+    let path = path.strip_prefix([' ', '*'])?;
+    if hash.len() != 64 || !hash.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    let path = if escaped {
+        unescape_path(path)
+    } else {
+        path.to_string()
+    };
+    Some((None, hash.to_lowercase(), path))
+}
+
+/// Reverse the `\\` and `\n` escapes written by `format_line`.
+fn unescape_path(path: &str) -> String {
+    let mut out = String::with_capacity(path.len());
+    let mut chars = path.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some('n') => out.push('\n'),
+                Some(other) => out.push(other),
+                None => out.push('\\'),
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// A manifest path in the form scanned files are compared against:
+/// relative to `roots` (the root as given and canonicalized) when it is
+/// absolute, without `.` components such as the `./` that `sha256sum ./*`
+/// writes, and with `/` separators.
+fn listed_path(rel: &str, roots: &[PathBuf]) -> String {
+    let path = Path::new(rel);
+    let path = roots
+        .iter()
+        .find_map(|root| path.strip_prefix(root).ok())
+        .unwrap_or(path);
+    path.components()
+        .filter(|c| !matches!(c, Component::CurDir))
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+pub fn run_checksum_create(
+    directory: &str,
+    output: Option<&str>,
+    algorithm: &str,
+    recursive: bool,
+) -> ChecksumCreateResult {
+    let mut errors = Vec::new();
+    let algorithm = match normalize_algorithm(algorithm) {
+        Some(a) => a,
+        None => {
+            errors.push(format!("Unknown algorithm: {}", algorithm));
+            return ChecksumCreateResult {
+                algorithm: algorithm.to_string(),
+                manifest: output.map(String::from),
+                files_hashed: 0,
+                total_size: 0,
+                manifest_text: String::new(),
+                errors,
+            };
+        }
+    };
+
+    let dir = Path::new(directory);
+    let output_path = output.map(PathBuf::from);
+    let output_canonical = output_path
+        .as_ref()
+        .and_then(|p| std::fs::canonicalize(p).ok());
+
+    let mut files = scan_directory(dir, recursive);
+    // Never checksum the manifest we're about to overwrite
+    if let Some(ref out) = output_canonical {
+        files.retain(|f| std::fs::canonicalize(&f.path).ok().as_ref() != Some(out));
+    }
+
+    let mut hashed: Vec<(String, Option<String>, u64)> = files
+        .par_iter()
+        .map(|f| {
+            let rel = f
+                .path
+                .strip_prefix(dir)
+                .unwrap_or(&f.path)
+                .to_string_lossy()
+                .replace('\\', "/");
+            (rel, digest_file(&f.path, f.size, algorithm), f.size)
+        })
+        .collect();
+    hashed.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut manifest_text = String::new();
+    let mut files_hashed = 0;
+    let mut total_size = 0;
+    for (rel, hash, size) in &hashed {
+        match hash {
+            Some(hash) => {
+                manifest_text.push_str(&format_line(hash, rel));
+                files_hashed += 1;
+                total_size += size;
+            }
+            None => errors.push(format!("Failed to read {}", rel)),
+        }
+    }
+
+    if let Some(ref out) = output_path
+        && let Err(e) = std::fs::write(out, &manifest_text)
+    {
+        errors.push(format!("Failed to write {}: {}", out.display(), e));
+    }
+
+    ChecksumCreateResult {
+        algorithm: algorithm.to_string(),
+        manifest: output.map(String::from),
+        files_hashed,
+        total_size,
+        manifest_text,
+        errors,
+    }
+}
+
+/// Verify files against a manifest. Relative paths resolve against `root`,
+/// defaulting to the directory containing the manifest.
+pub fn run_checksum_verify(
+    manifest: &str,
+    root: Option<&str>,
+    algorithm: Option<&str>,
+    recursive: bool,
+) -> ChecksumVerifyResult {
+    let manifest_path = Path::new(manifest);
+    let root = root.map(PathBuf::from).unwrap_or_else(|| {
+        manifest_path
+            .parent()
+            .filter(|p| !p.as_os_str().is_empty())
+            .unwrap_or(Path::new("."))
+            .to_path_buf()
+    });
+
+    let mut result = ChecksumVerifyResult {
+        manifest: manifest.to_string(),
+        algorithm: String::new(),
+        ok: 0,
+        changed: Vec::new(),
+        missing: Vec::new(),
+        new: Vec::new(),
+        errors: Vec::new(),
+    };
+
+    let default_algorithm = match algorithm {
+        Some(name) => match normalize_algorithm(name) {
+            Some(a) => a,
+            None => {
+                result.errors.push(format!("Unknown algorithm: {}", name));
+                return result;
+            }
+        },
+        None => algorithm_from_name(manifest_path),
+    };
+
+    let text = match std::fs::read_to_string(manifest_path) {
+        Ok(t) => t,
+        Err(e) => {
+            result
+                .errors
+                .push(format!("Failed to read {}: {}", manifest, e));
+            return result;
+        }
+    };
+
+    let mut entries = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match parse_line(line) {
+            Some((tag, hash, rel)) => entries.push((tag.unwrap_or(default_algorithm), hash, rel)),
+            None => {
+                result
+                    .errors
+                    .push(format!("{}:{}: improperly formatted line", manifest, i + 1))
+            }
+        }
+    }
+    result.algorithm = entries
+        .first()
+        .map(|e| e.0)
+        .unwrap_or(default_algorithm)
+        .to_string();
+
+    enum Outcome {
+        Ok,
+        Changed,
+        Missing,
+    }
+
+    let outcomes: Vec<Outcome> = entries
+        .par_iter()
+        .map(|(algorithm, expected, rel)| {
+            let path = root.join(rel);
+            match std::fs::metadata(&path) {
+                Ok(meta) if meta.is_file() => match digest_file(&path, meta.len(), algorithm) {
+                    Some(actual) if &actual == expected => Outcome::Ok,
+                    _ => Outcome::Changed,
+                },
+                _ => Outcome::Missing,
+            }
+        })
+        .collect();
+
+    for ((_, _, rel), outcome) in entries.iter().zip(outcomes) {
+        match outcome {
+            Outcome::Ok => result.ok += 1,
+            Outcome::Changed => result.changed.push(rel.clone()),
+            Outcome::Missing => result.missing.push(rel.clone()),
+        }
+    }
+
+    // Files on disk that the manifest doesn't mention
+    let roots: Vec<PathBuf> = [std::path::absolute(&root), root.canonicalize()]
+        .into_iter()
+        .filter_map(Result::ok)
+        .collect();
+    let listed: HashSet<String> = entries
+        .iter()
+        .map(|(_, _, rel)| listed_path(rel, &roots))
+        .collect();
+    let manifest_canonical = std::fs::canonicalize(manifest_path).ok();
+    let mut new: Vec<String> = scan_directory(&root, recursive)
+        .into_iter()
+        .filter(|f| std::fs::canonicalize(&f.path).ok() != manifest_canonical)
+        .map(|f| {
+            f.path
+                .strip_prefix(&root)
+                .unwrap_or(&f.path)
+                .to_string_lossy()
+                .replace('\\', "/")
+        })
+        .filter(|rel| !listed.contains(rel.as_str()))
+        .collect();
+    new.sort();
+    result.new = new;
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_line_formats() {
+        let hash = "a".repeat(64);
+        assert_eq!(
+            parse_line(&format!("{}  dir/file.txt", hash)),
+            Some((None, hash.clone(), "dir/file.txt".to_string()))
+        );
+        assert_eq!(
+            parse_line(&format!("{} *binary.dat", hash)),
+            Some((None, hash.clone(), "binary.dat".to_string()))
+        );
+        assert_eq!(
+            parse_line(&format!("SHA256 (a b.txt) = {}", hash)),
+            Some((Some("sha256"), hash.clone(), "a b.txt".to_string()))
+        );
+        assert_eq!(parse_line("not a checksum line"), None);
+    }
+
+    #[test]
+    fn test_escaped_path_roundtrip() {
+        let hash = "0".repeat(64);
+        let line = format_line(&hash, "odd\\name\nwith newline");
+        assert!(line.starts_with('\\'));
+        let (_, parsed_hash, path) = parse_line(line.trim_end_matches('\n')).unwrap();
+        assert_eq!(parsed_hash, hash);
+        assert_eq!(path, "odd\\name\nwith newline");
+    }
+}
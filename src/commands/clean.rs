@@ -0,0 +1,337 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+
+use globset::{Glob, GlobSetBuilder};
+use serde::Serialize;
+
+use crate::journal::{self, Journal, JournalEntry, now_timestamp};
+use crate::scanner::{FileInfo, scan_entries};
+use crate::transfer::{create_dirs, move_path};
+
+/// Names treated as junk unless `--no-default-junk` is given: Finder and
+/// Explorer metadata, editor backup and swap files, Python bytecode caches.
+pub const DEFAULT_JUNK: &[&str] = &[
+    ".DS_Store",
+    "Thumbs.db",
+    "desktop.ini",
+    "*~",
+    "#*#",
+    ".*.swp",
+    ".*.swo",
+    "__pycache__",
+];
+
+/// Every kind of entry clean reports.
+pub const KINDS: &[&str] = &["empty-files", "empty-dirs", "broken-symlinks", "junk"];
+
+/// Version control metadata is never looked into: its empty files and
+/// directories are part of the repository.
+const VCS_DIRS: &[&str] = &[".git", ".hg", ".svn"];
+
+/// Empty files that mean something by existing.
+const KEEP_EMPTY: &[&str] = &[".gitkeep", ".keep", "__init__.py", "py.typed"];
+
+#[derive(Debug, Serialize)]
+pub struct CleanResult {
+    pub entries_scanned: usize,
+    pub empty_files: Vec<String>,
+    /// Directories holding nothing but empty directories, topmost only
+    pub empty_dirs: Vec<String>,
+    pub broken_symlinks: Vec<BrokenLink>,
+    pub junk: Vec<JunkEntry>,
+    /// Bytes held by junk, including the contents of junk directories
+    pub junk_size: u64,
+    /// Only reported unless moving to the trash was requested
+    pub dry_run: bool,
+    /// Entries moved to the trash
+    pub trashed: usize,
+    /// Journal id for `fiq undo`, set when anything was trashed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub run_id: Option<String>,
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BrokenLink {
+    pub path: String,
+    pub target: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JunkEntry {
+    pub path: String,
+    pub size: u64,
+    /// The junk pattern that matched
+    pub pattern: String,
+}
+
+pub struct CleanParams<'a> {
+    pub directory: &'a str,
+    /// Kinds to report; empty for all of `KINDS`
+    pub kinds: &'a [String],
+    /// Extra junk name globs
+    pub junk: &'a [String],
+    /// Use only `junk`, not `DEFAULT_JUNK`
+    pub no_default_junk: bool,
+    /// Move everything reported to the trash
+    pub trash: bool,
+    pub recursive: bool,
+}
+
+/// Mark every ancestor of `path` as holding something.
+fn mark_parents(path: &Path, occupied: &mut HashSet<PathBuf>) {
+    for parent in path.ancestors().skip(1) {
+        if !occupied.insert(parent.to_path_buf()) {
+            break;
+        }
+    }
+}
+
+/// Whether `dir` contains nothing but empty directories. Unreadable
+/// directories are not empty, as far as we know.
+fn is_empty_tree(dir: &Path) -> bool {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return false;
+    };
+    entries.into_iter().all(|e| {
+        e.is_ok_and(|e| e.file_type().is_ok_and(|t| t.is_dir()) && is_empty_tree(&e.path()))
+    })
+}
+
+/// Where a path goes inside a run's trash: its absolute path, minus the
+/// root or drive, so nothing collides and the origin stays readable.
+fn trash_path(trash: &Path, path: &Path) -> PathBuf {
+    let mut dest = trash.to_path_buf();
+    for component in path.components() {
+        if let Component::Normal(part) = component {
+            dest.push(part);
+        }
+    }
+    dest
+}
+
+pub fn run_clean(params: &CleanParams<'_>) -> CleanResult {
+    let CleanParams {
+        directory,
+        kinds,
+        junk,
+        no_default_junk,
+        trash,
+        recursive,
+    } = *params;
+    let mut result = CleanResult {
+        entries_scanned: 0,
+        empty_files: Vec::new(),
+        empty_dirs: Vec::new(),
+        broken_symlinks: Vec::new(),
+        junk: Vec::new(),
+        junk_size: 0,
+        dry_run: !trash,
+        trashed: 0,
+        run_id: None,
+        errors: Vec::new(),
+    };
+
+    if let Some(kind) = kinds.iter().find(|k| !KINDS.contains(&k.as_str())) {
+        result.errors.push(format!(
+            "Unknown kind: {} (expected one of {})",
+            kind,
+            KINDS.join(", ")
+        ));
+        return result;
+    }
+    let wanted = |kind: &str| kinds.is_empty() || kinds.iter().any(|k| k == kind);
+
+    let patterns: Vec<&str> = if no_default_junk {
+        Vec::new()
+    } else {
+        DEFAULT_JUNK.to_vec()
+    }
+    .into_iter()
+    .chain(junk.iter().map(String::as_str))
+    .collect();
+    let mut builder = GlobSetBuilder::new();
+    for pattern in &patterns {
+        match Glob::new(pattern) {
+            Ok(glob) => {
+                builder.add(glob);
+            }
+            Err(e) => {
+                result
+                    .errors
+                    .push(format!("Invalid junk pattern {}: {}", pattern, e));
+                return result;
+            }
+        }
+    }
+    let junk_set = match builder.build() {
+        Ok(set) => set,
+        Err(e) => {
+            result.errors.push(format!("Invalid junk patterns: {}", e));
+            return result;
+        }
+    };
+
+    let dir = Path::new(directory);
+    let mut entries = scan_entries(dir, recursive);
+    result.entries_scanned = entries.len();
+    // Parents before their contents
+    entries.sort_by(|a, b| a.path.cmp(&b.path));
+
+    // Directories that hold something other than empty directories
+    let mut occupied: HashSet<PathBuf> = HashSet::new();
+    let mut skipped: HashSet<&Path> = HashSet::new();
+    let mut junk_dirs: HashMap<&Path, usize> = HashMap::new();
+    let mut dirs: Vec<&FileInfo> = Vec::new();
+
+    for entry in &entries {
+        let path = entry.path.as_path();
+        if path.ancestors().skip(1).any(|a| skipped.contains(a)) {
+            continue;
+        }
+        if let Some(&index) = path.ancestors().skip(1).find_map(|a| junk_dirs.get(a)) {
+            if !entry.is_dir {
+                result.junk[index].size += entry.size;
+            }
+            continue;
+        }
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+        if entry.is_dir && VCS_DIRS.contains(&name) {
+            skipped.insert(path);
+            mark_parents(path, &mut occupied);
+            continue;
+        }
+        if !entry.is_dir {
+            mark_parents(path, &mut occupied);
+        }
+
+        if let Some(&index) = junk_set.matches(name).first() {
+            mark_parents(path, &mut occupied);
+            if entry.is_dir {
+                junk_dirs.insert(path, result.junk.len());
+            }
+            result.junk.push(JunkEntry {
+                path: path.display().to_string(),
+                size: if entry.is_dir { 0 } else { entry.size },
+                pattern: patterns[index].to_string(),
+            });
+        } else if entry.is_symlink {
+            if std::fs::metadata(path).is_err() {
+                result.broken_symlinks.push(BrokenLink {
+                    path: path.display().to_string(),
+                    target: std::fs::read_link(path)
+                        .map(|t| t.display().to_string())
+                        .unwrap_or_default(),
+                });
+            }
+        } else if entry.is_dir {
+            dirs.push(entry);
+        } else if entry.size == 0
+            && !KEEP_EMPTY.contains(&name)
+            // FIFOs, sockets and device nodes have no length either
+            && std::fs::symlink_metadata(path).is_ok_and(|m| m.file_type().is_file())
+        {
+            result.empty_files.push(path.display().to_string());
+        }
+    }
+
+    // Report the topmost of nested empty directories; the walk skips what
+    // it cannot read, so each one is checked on disk before it is listed
+    for entry in dirs {
+        let path = entry.path.as_path();
+        let topmost = path
+            .parent()
+            .is_none_or(|p| p == dir || occupied.contains(p));
+        if !occupied.contains(path) && topmost && is_empty_tree(path) {
+            result.empty_dirs.push(path.display().to_string());
+        }
+    }
+
+    if !wanted("empty-files") {
+        result.empty_files.clear();
+    }
+    if !wanted("empty-dirs") {
+        result.empty_dirs.clear();
+    }
+    if !wanted("broken-symlinks") {
+        result.broken_symlinks.clear();
+    }
+    if !wanted("junk") {
+        result.junk.clear();
+    }
+    result.junk_size = result.junk.iter().map(|j| j.size).sum();
+
+    if trash {
+        move_to_trash(&mut result);
+    }
+    result
+}
+
+/// Move every reported entry into a trash folder for this run under the
+/// state directory, journaling each move so `fiq undo` can put it back.
+fn move_to_trash(result: &mut CleanResult) {
+    let paths: Vec<PathBuf> = result
+        .junk
+        .iter()
+        .map(|j| &j.path)
+        .chain(result.broken_symlinks.iter().map(|l| &l.path))
+        .chain(&result.empty_files)
+        .chain(&result.empty_dirs)
+        .map(|p| std::path::absolute(p).unwrap_or_else(|_| PathBuf::from(p)))
+        .collect();
+    if paths.is_empty() {
+        return;
+    }
+
+    // The trash folder is named after the run, so claim the id first
+    let mut journal = Journal::new();
+    if let Err(e) = journal.open() {
+        result
+            .errors
+            .push(format!("Failed to write undo journal: {}", e));
+        return;
+    }
+    let Some(trash) = journal::state_dir().map(|d| d.join("trash").join(journal.run_id())) else {
+        result
+            .errors
+            .push("No state directory for the trash".to_string());
+        return;
+    };
+    for from in paths {
+        let to = trash_path(&trash, &from);
+        let created = to.parent().map_or(Ok(Vec::new()), create_dirs);
+        // Journaled so undo leaves no empty folders behind in the trash
+        for dir in created.as_deref().unwrap_or_default() {
+            if let Err(e) = journal.record(&JournalEntry::mkdir(dir)) {
+                result
+                    .errors
+                    .push(format!("Failed to write undo journal: {}", e));
+            }
+        }
+        let moved = created.and_then(|_| move_path(&from, &to));
+        if let Err(e) = moved {
+            result
+                .errors
+                .push(format!("Failed to trash {}: {}", from.display(), e));
+            continue;
+        }
+
+        let meta = std::fs::symlink_metadata(&to).ok();
+        let entry = JournalEntry {
+            timestamp: now_timestamp(),
+            action: "trash".to_string(),
+            from: from.display().to_string(),
+            to: to.display().to_string(),
+            size: meta.as_ref().map_or(0, |m| m.len()),
+            modified: meta.and_then(|m| m.modified().ok()),
+            hash: None,
+        };
+        if let Err(e) = journal.record(&entry) {
+            result
+                .errors
+                .push(format!("Failed to write undo journal: {}", e));
+        }
+        result.trashed += 1;
+    }
+    result.run_id = journal.is_written().then(|| journal.run_id().to_string());
+}
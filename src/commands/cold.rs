@@ -0,0 +1,166 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::SystemTime;
+
+use rayon::prelude::*;
+use serde::Serialize;
+
+use crate::commands::search::{parse_size, parse_time};
+use crate::scanner::scan_directory;
+
+const SECS_PER_DAY: u64 = 86_400;
+
+#[derive(Debug, Serialize)]
+pub struct ColdResult {
+    pub files_scanned: usize,
+    /// Files at least `min_size` bytes, last used before the cutoff
+    pub cold_files: usize,
+    pub total_size: u64,
+    /// Ages count from the last access rather than the last modification
+    pub by_access: bool,
+    /// The coldest files, by size × age
+    pub files: Vec<ColdFile>,
+    /// Cold data per top-level directory, largest first
+    pub by_directory: Vec<ColdDir>,
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ColdFile {
+    pub path: String,
+    pub size: u64,
+    /// Date of last use, YYYY-MM-DD
+    pub last_used: String,
+    pub age_days: u64,
+    /// Size in GB × age in days; files are ranked by it
+    pub score: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ColdDir {
+    pub path: String,
+    pub files: usize,
+    pub size: u64,
+}
+
+pub struct ColdParams<'a> {
+    pub directory: &'a str,
+    /// Last used before this: a date or a relative time such as "180d"
+    pub older: &'a str,
+    /// Smallest file to report, e.g. "100MB"
+    pub min_size: &'a str,
+    /// Number of files to list
+    pub top_n: usize,
+    /// Age files by access time where the file system records it
+    pub by_access: bool,
+    pub recursive: bool,
+}
+
+/// When a file was last used: its mtime, or with `by_access` the later of
+/// atime and mtime. Writing a file is using it, and on `noatime` mounts
+/// the atime never moves past the creation time.
+fn last_used(path: &Path, modified: Option<SystemTime>, by_access: bool) -> Option<SystemTime> {
+    if !by_access {
+        return modified;
+    }
+    let accessed = std::fs::metadata(path).and_then(|m| m.accessed()).ok();
+    match (accessed, modified) {
+        (Some(a), Some(m)) => Some(a.max(m)),
+        (a, m) => a.or(m),
+    }
+}
+
+pub fn run_cold(params: &ColdParams<'_>) -> ColdResult {
+    let ColdParams {
+        directory,
+        older,
+        min_size,
+        top_n,
+        by_access,
+        recursive,
+    } = *params;
+    let mut result = ColdResult {
+        files_scanned: 0,
+        cold_files: 0,
+        total_size: 0,
+        by_access,
+        files: Vec::new(),
+        by_directory: Vec::new(),
+        errors: Vec::new(),
+    };
+
+    let Some(cutoff) = parse_time(older) else {
+        result.errors.push(format!(
+            "Invalid age: {} (expected e.g. \"180d\" or \"2024-01-01\")",
+            older
+        ));
+        return result;
+    };
+    let Some(min_size) = parse_size(min_size) else {
+        result.errors.push(format!(
+            "Invalid size: {} (expected e.g. \"100MB\")",
+            min_size
+        ));
+        return result;
+    };
+
+    let dir = Path::new(directory);
+    let files = scan_directory(dir, recursive);
+    result.files_scanned = files.len();
+
+    // atime is only read for files that pass the size filter
+    let now = SystemTime::now();
+    let mut cold: Vec<(&Path, u64, SystemTime)> = files
+        .par_iter()
+        .filter(|f| f.size >= min_size)
+        .filter_map(|f| {
+            let used = last_used(&f.path, f.modified, by_access)?;
+            (used < cutoff).then_some((f.path.as_path(), f.size, used))
+        })
+        .collect();
+
+    let age_days = |used: SystemTime| {
+        now.duration_since(used)
+            .map(|d| d.as_secs() / SECS_PER_DAY)
+            .unwrap_or(0)
+    };
+    let score = |size: u64, used: SystemTime| size as f64 / 1e9 * age_days(used) as f64;
+    cold.sort_by(|a, b| score(b.1, b.2).total_cmp(&score(a.1, a.2)));
+
+    let mut dir_map: HashMap<String, (usize, u64)> = HashMap::new();
+    for &(path, size, _) in &cold {
+        // Files directly in the scanned directory count towards it
+        let rel = path.strip_prefix(dir).unwrap_or(path);
+        let top = match rel.components().next() {
+            Some(first) if rel.components().nth(1).is_some() => dir.join(first),
+            _ => dir.to_path_buf(),
+        };
+        let entry = dir_map.entry(top.display().to_string()).or_insert((0, 0));
+        entry.0 += 1;
+        entry.1 += size;
+    }
+    result.by_directory = dir_map
+        .into_iter()
+        .map(|(path, (files, size))| ColdDir { path, files, size })
+        .collect();
+    result
+        .by_directory
+        .sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.path.cmp(&b.path)));
+
+    result.cold_files = cold.len();
+    result.total_size = cold.iter().map(|&(_, size, _)| size).sum();
+    result.files = cold
+        .into_iter()
+        .take(top_n)
+        .map(|(path, size, used)| ColdFile {
+            path: path.display().to_string(),
+            size,
+            last_used: chrono::DateTime::<chrono::Local>::from(used)
+                .format("%Y-%m-%d")
+                .to_string(),
+            age_days: age_days(used),
+            score: score(size, used),
+        })
+        .collect();
+    result
+}
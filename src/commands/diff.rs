@@ -0,0 +1,213 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use rayon::prelude::*;
+use serde::Serialize;
+
+use crate::commands::duplicates::hash_file;
+use crate::scanner::{FileInfo, scan_directory};
+
+/// Modification times closer than this are considered equal. FAT and some
+/// network filesystems only store mtimes with 2-second precision.
+const MTIME_TOLERANCE: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Serialize)]
+pub struct DiffResult {
+    pub left: String,
+    pub right: String,
+    pub only_left: Vec<DiffEntry>,
+    pub only_right: Vec<DiffEntry>,
+    pub modified: Vec<ModifiedEntry>,
+    pub moved: Vec<MovedEntry>,
+    pub same: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DiffEntry {
+    pub path: String,
+    pub size: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ModifiedEntry {
+    pub path: String,
+    pub left_size: u64,
+    pub right_size: u64,
+    /// What differs: "size", "mtime", or "content" (verified by hash)
+    pub reason: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MovedEntry {
+    pub from: String,
+    pub to: String,
+    pub size: u64,
+}
+
+fn relative_map<'a>(root: &Path, files: &'a [FileInfo]) -> HashMap<PathBuf, &'a FileInfo> {
+    files
+        .iter()
+        .map(|f| {
+            (
+                f.path.strip_prefix(root).unwrap_or(&f.path).to_path_buf(),
+                f,
+            )
+        })
+        .collect()
+}
+
+fn mtime_differs(a: Option<SystemTime>, b: Option<SystemTime>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => {
+            let delta = a.duration_since(b).or_else(|_| b.duration_since(a));
+            delta.is_ok_and(|d| d > MTIME_TOLERANCE)
+        }
+        _ => false,
+    }
+}
+
+/// Compare two directory trees, pairing files by relative path.
+///
+/// Without `verify`, a pair counts as modified when size or mtime differ.
+/// With `verify`, same-size pairs are hashed with blake3 and only content
+/// decides — touched-but-identical files are reported as same.
+/// Files present on only one side are matched by size then hash to
+/// detect moves and renames between the trees.
+pub fn run_diff(left: &str, right: &str, verify: bool, recursive: bool) -> DiffResult {
+    let left_dir = Path::new(left);
+    let right_dir = Path::new(right);
+    let left_files = scan_directory(left_dir, recursive);
+    let right_files = scan_directory(right_dir, recursive);
+    let left_map = relative_map(left_dir, &left_files);
+    let right_map = relative_map(right_dir, &right_files);
+
+    let mut only_left: Vec<(&PathBuf, &FileInfo)> = Vec::new();
+    let mut paired: Vec<(&PathBuf, &FileInfo, &FileInfo)> = Vec::new();
+    for (rel, lf) in &left_map {
+        match right_map.get(rel) {
+            Some(rf) => paired.push((rel, lf, rf)),
+            None => only_left.push((rel, lf)),
+        }
+    }
+    let mut only_right: Vec<(&PathBuf, &FileInfo)> = right_map
+        .iter()
+        .filter(|(rel, _)| !left_map.contains_key(*rel))
+        .map(|(rel, f)| (rel, *f))
+        .collect();
+
+    // Classify pairs; hashing (if any) runs in parallel
+    let classified: Vec<Option<ModifiedEntry>> = paired
+        .par_iter()
+        .map(|(rel, lf, rf)| {
+            let reason = if lf.size != rf.size {
+                Some("size")
+            } else if verify {
+                let lh = hash_file(&lf.path, lf.size);
+                let rh = hash_file(&rf.path, rf.size);
+                (lh.is_none() || lh != rh).then_some("content")
+            } else if mtime_differs(lf.modified, rf.modified) {
+                Some("mtime")
+            } else {
+                None
+            };
+            reason.map(|reason| ModifiedEntry {
+                path: rel.display().to_string(),
+                left_size: lf.size,
+                right_size: rf.size,
+                reason,
+            })
+        })
+        .collect();
+
+    let same = classified.iter().filter(|c| c.is_none()).count();
+    let mut modified: Vec<ModifiedEntry> = classified.into_iter().flatten().collect();
+    modified.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let moved = detect_moves(&mut only_left, &mut only_right);
+
+    let to_entries = |mut list: Vec<(&PathBuf, &FileInfo)>| {
+        list.sort_by(|a, b| a.0.cmp(b.0));
+        list.into_iter()
+            .map(|(rel, f)| DiffEntry {
+                path: rel.display().to_string(),
+                size: f.size,
+            })
+            .collect::<Vec<_>>()
+    };
+
+    DiffResult {
+        left: left.to_string(),
+        right: right.to_string(),
+        only_left: to_entries(only_left),
+        only_right: to_entries(only_right),
+        modified,
+        moved,
+        same,
+    }
+}
+
+/// Pair up one-sided files with identical content. Matched files are
+/// removed from `only_left`/`only_right` and returned as moves.
+fn detect_moves(
+    only_left: &mut Vec<(&PathBuf, &FileInfo)>,
+    only_right: &mut Vec<(&PathBuf, &FileInfo)>,
+) -> Vec<MovedEntry> {
+    let right_sizes: HashSet<u64> = only_right.iter().map(|(_, f)| f.size).collect();
+
+    // Only hash files whose size appears on both sides
+    let left_hashes = hash_side(only_left, |s| right_sizes.contains(&s));
+    let left_sizes: HashSet<u64> = left_hashes
+        .iter()
+        .map(|(_, i)| only_left[*i].1.size)
+        .collect();
+    let right_hashes = hash_side(only_right, |s| left_sizes.contains(&s));
+
+    let mut right_by_hash: HashMap<String, Vec<usize>> = HashMap::new();
+    for (hash, i) in right_hashes {
+        right_by_hash.entry(hash).or_default().push(i);
+    }
+
+    let mut moved = Vec::new();
+    let mut taken_left = Vec::new();
+    let mut taken_right = Vec::new();
+    for (hash, li) in left_hashes {
+        if let Some(candidates) = right_by_hash.get_mut(&hash)
+            && let Some(ri) = candidates.pop()
+        {
+            moved.push(MovedEntry {
+                from: only_left[li].0.display().to_string(),
+                to: only_right[ri].0.display().to_string(),
+                size: only_left[li].1.size,
+            });
+            taken_left.push(li);
+            taken_right.push(ri);
+        }
+    }
+
+    remove_indices(only_left, taken_left);
+    remove_indices(only_right, taken_right);
+
+    moved.sort_by(|a, b| a.from.cmp(&b.from));
+    moved
+}
+
+/// Hash the files on one side whose size passes `wanted`, returning (hash, index).
+fn hash_side(
+    side: &[(&PathBuf, &FileInfo)],
+    wanted: impl Fn(u64) -> bool + Sync,
+) -> Vec<(String, usize)> {
+    side.par_iter()
+        .enumerate()
+        .filter(|(_, (_, f))| wanted(f.size))
+        .filter_map(|(i, (_, f))| Some((hash_file(&f.path, f.size)?, i)))
+        .collect()
+}
+
+fn remove_indices<T>(list: &mut Vec<T>, mut indices: Vec<usize>) {
+    // Highest first so swap_remove never disturbs a pending index
+    indices.sort_unstable_by(|a, b| b.cmp(a));
+    for i in indices {
+        list.swap_remove(i);
+    }
+}
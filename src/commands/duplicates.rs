@@ -15,6 +15,8 @@ const MMAP_THRESHOLD: u64 = 128 * 1024; // 128 KB
 pub struct DuplicatesResult {
     pub total_files_scanned: usize,
     pub duplicate_groups: Vec<DuplicateGroup>,
+    /// Wasted bytes are allocated disk space rather than apparent size
+    pub disk_usage: bool,
     pub total_wasted_bytes: u64,
 }
 
@@ -23,10 +25,13 @@ pub struct DuplicateGroup {
     pub hash: String,
     pub size: u64,
     pub files: Vec<String>,
+    /// Space freed by keeping only one copy. With disk usage, the copy
+    /// taking the most space is assumed kept.
+    pub wasted_bytes: u64,
 }
 
 /// Hash a file using blake3. Uses mmap for large files.
-fn hash_file(path: &Path, size: u64) -> Option<String> {
+pub fn hash_file(path: &Path, size: u64) -> Option<String> {
     if size == 0 {
         return Some(blake3::hash(b"").to_hex().to_string());
     }
@@ -45,7 +50,12 @@ fn hash_file(path: &Path, size: u64) -> Option<String> {
     }
 }
 
-pub fn run_duplicates(directory: &str, min_size: u64, recursive: bool) -> DuplicatesResult {
+pub fn run_duplicates(
+    directory: &str,
+    min_size: u64,
+    recursive: bool,
+    disk_usage: bool,
+) -> DuplicatesResult {
     let dir = Path::new(directory);
     let files = scan_directory(dir, recursive);
 
@@ -60,7 +70,7 @@ pub fn run_duplicates(directory: &str, min_size: u64, recursive: bool) -> Duplic
     }
 
     // Step 2: Hash candidates in parallel (only files sharing a size with others)
-    let hashed: Vec<(String, String, u64)> = size_groups
+    let hashed: Vec<(String, String, u64, u64)> = size_groups
         .into_values()
         .filter(|group| group.len() > 1)
         .flatten()
@@ -68,38 +78,48 @@ pub fn run_duplicates(directory: &str, min_size: u64, recursive: bool) -> Duplic
         .par_iter()
         .filter_map(|file| {
             let hash = hash_file(&file.path, file.size)?;
-            Some((hash, file.path.display().to_string(), file.size))
+            Some((
+                hash,
+                file.path.display().to_string(),
+                file.size,
+                file.size_in(disk_usage),
+            ))
         })
         .collect();
 
-    // Step 3: Group by hash
-    let mut hash_groups: HashMap<String, (u64, Vec<String>)> = HashMap::new();
-    for (hash, path, size) in hashed {
-        let entry = hash_groups.entry(hash).or_insert((size, Vec::new()));
+    // Step 3: Group by hash, keeping each copy's measured size
+    let mut hash_groups: HashMap<String, (u64, Vec<String>, Vec<u64>)> = HashMap::new();
+    for (hash, path, size, measured) in hashed {
+        let entry = hash_groups
+            .entry(hash)
+            .or_insert((size, Vec::new(), Vec::new()));
         entry.1.push(path);
+        entry.2.push(measured);
     }
 
     // Only keep actual duplicates (2+ files with same hash)
     let mut duplicate_groups: Vec<DuplicateGroup> = hash_groups
         .into_iter()
-        .filter(|(_, (_, files))| files.len() > 1)
-        .map(|(hash, (size, files))| DuplicateGroup { hash, size, files })
+        .filter(|(_, (_, files, _))| files.len() > 1)
+        .map(|(hash, (size, files, measured))| {
+            let kept = measured.iter().copied().max().unwrap_or(0);
+            DuplicateGroup {
+                hash,
+                size,
+                files,
+                wasted_bytes: measured.iter().sum::<u64>() - kept,
+            }
+        })
         .collect();
 
-    duplicate_groups.sort_by(|a, b| {
-        let a_waste = a.size * (a.files.len() as u64 - 1);
-        let b_waste = b.size * (b.files.len() as u64 - 1);
-        b_waste.cmp(&a_waste)
-    });
+    duplicate_groups.sort_by_key(|g| std::cmp::Reverse(g.wasted_bytes));
 
-    let total_wasted_bytes: u64 = duplicate_groups
-        .iter()
-        .map(|g| g.size * (g.files.len() as u64 - 1))
-        .sum();
+    let total_wasted_bytes: u64 = duplicate_groups.iter().map(|g| g.wasted_bytes).sum();
 
     DuplicatesResult {
         total_files_scanned,
         duplicate_groups,
+        disk_usage,
         total_wasted_bytes,
     }
 }
@@ -0,0 +1,172 @@
+use std::path::{Path, PathBuf};
+
+use rayon::prelude::*;
+use serde::Serialize;
+
+use crate::commands::duplicates::hash_file;
+use crate::hash_cache::HashCache;
+use crate::scanner::{FileInfo, scan_directory};
+
+#[derive(Debug, Serialize)]
+pub struct FindCopiesResult {
+    pub hash: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+    pub matches: Vec<CopyMatch>,
+    pub files_scanned: usize,
+    pub files_hashed: usize,
+    pub from_cache: bool,
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CopyMatch {
+    pub path: String,
+    pub size: u64,
+}
+
+/// What to look for: a file on disk (hash and size known) or a bare digest.
+enum Target {
+    File {
+        path: PathBuf,
+        size: u64,
+        hash: String,
+    },
+    Digest(String),
+}
+
+fn resolve_target(target: &str) -> Result<Target, String> {
+    let path = Path::new(target);
+    if let Ok(meta) = std::fs::metadata(path)
+        && meta.is_file()
+    {
+        let hash =
+            hash_file(path, meta.len()).ok_or_else(|| format!("Failed to read {}", target))?;
+        let path = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        return Ok(Target::File {
+            path,
+            size: meta.len(),
+            hash,
+        });
+    }
+
+    if target.len() == 64 && target.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Ok(Target::Digest(target.to_lowercase()));
+    }
+
+    Err(format!(
+        "Not a file or a blake3 hex digest (64 hex characters): {}",
+        target
+    ))
+}
+
+/// Find every file under `directories` whose blake3 hash matches `target`.
+///
+/// For a file target, only files of the same size are hashed. For a bare
+/// digest every file must be hashed, unless a complete, fresh hash cache
+/// exists for the directory. Hashes computed along the way are saved to
+/// the cache so later lookups skip unchanged files.
+pub fn run_find_copies(
+    target: &str,
+    directories: &[String],
+    use_cache: bool,
+    recursive: bool,
+) -> FindCopiesResult {
+    let mut result = FindCopiesResult {
+        hash: String::new(),
+        size: None,
+        matches: Vec::new(),
+        files_scanned: 0,
+        files_hashed: 0,
+        from_cache: false,
+        errors: Vec::new(),
+    };
+
+    let target = match resolve_target(target) {
+        Ok(t) => t,
+        Err(e) => {
+            result.errors.push(e);
+            return result;
+        }
+    };
+    let (hash, size, source) = match &target {
+        Target::File { path, size, hash } => (hash.clone(), Some(*size), Some(path.clone())),
+        Target::Digest(hash) => (hash.clone(), None, None),
+    };
+    result.hash = hash.clone();
+    result.size = size;
+
+    let mut all_from_cache = true;
+    for directory in directories {
+        let root = std::fs::canonicalize(directory).unwrap_or_else(|_| PathBuf::from(directory));
+        if !root.is_dir() {
+            result
+                .errors
+                .push(format!("Not a directory: {}", directory));
+            continue;
+        }
+
+        let mut cache = if use_cache {
+            HashCache::load_cached(&root).unwrap_or_else(|| HashCache::new(&root))
+        } else {
+            HashCache::new(&root)
+        };
+
+        // Fast path: a complete cache answers digest queries without a walk
+        if use_cache && recursive && size.is_none() && cache.complete && cache.is_fresh() {
+            for (path, size) in cache.lookup(&hash) {
+                result.matches.push(CopyMatch {
+                    path: path.display().to_string(),
+                    size,
+                });
+            }
+            continue;
+        }
+        all_from_cache = false;
+
+        let files = scan_directory(&root, recursive);
+        result.files_scanned += files.len();
+
+        let candidates: Vec<&FileInfo> = files
+            .iter()
+            .filter(|f| size.is_none_or(|s| f.size == s))
+            .collect();
+
+        let hashed: Vec<(&FileInfo, Option<String>, bool)> = candidates
+            .par_iter()
+            .map(|f| match cache.get(&f.path, f.size, f.modified) {
+                Some(h) => (*f, Some(h.to_string()), false),
+                None => (*f, hash_file(&f.path, f.size), true),
+            })
+            .collect();
+
+        // A full pass rebuilds the cache so entries for deleted files don't linger
+        let full_pass = size.is_none() && recursive;
+        if full_pass {
+            cache = HashCache::new(&root);
+            cache.complete = true;
+        }
+
+        for (file, file_hash, fresh) in hashed {
+            let Some(file_hash) = file_hash else { continue };
+            if fresh {
+                result.files_hashed += 1;
+            }
+            if file_hash == hash && source.as_ref() != Some(&file.path) {
+                result.matches.push(CopyMatch {
+                    path: file.path.display().to_string(),
+                    size: file.size,
+                });
+            }
+            cache.insert(file.path.clone(), file.size, file.modified, file_hash);
+        }
+
+        if use_cache {
+            let _ = cache.save_to_cache();
+        }
+    }
+
+    result.from_cache = all_from_cache && !directories.is_empty() && result.errors.is_empty();
+    result.matches.sort_by(|a, b| a.path.cmp(&b.path));
+    result
+}
@@ -1,4 +1,13 @@
+pub mod checksum;
+pub mod clean;
+pub mod cold;
+pub mod diff;
 pub mod duplicates;
+pub mod find_copies;
 pub mod organize;
+pub mod rename;
 pub mod search;
+pub mod similar;
 pub mod stats;
+pub mod undo;
+pub mod watch;
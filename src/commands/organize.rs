@@ -1,15 +1,38 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 
+use rayon::prelude::*;
 use serde::Serialize;
 
-use crate::scanner::scan_directory;
+use crate::commands::duplicates::hash_file;
+use crate::commands::undo::revert;
+use crate::journal::{self, Journal, JournalEntry, now_timestamp};
+use crate::languages::language_for;
+use crate::media_date::capture_time;
+use crate::rules::RuleSet;
+use crate::scanner::{FileInfo, scan_directory};
+use crate::sniff::sniff;
+use crate::template::{Template, Values};
+use crate::transfer::{create_dirs, move_path};
 
 #[derive(Debug, Serialize)]
 pub struct OrganizeResult {
     pub total_files: usize,
     pub moves: Vec<FileMove>,
+    /// Files whose destination already held identical content (`dedupe`
+    /// mode). With the move action the source copy is removed.
+    pub identical: Vec<FileMove>,
+    /// How files were placed: move, copy, hardlink or symlink
+    pub action: String,
     pub dry_run: bool,
+    /// Journal id for `fiq undo`, set when a real run moved anything
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub run_id: Option<String>,
+    /// The run failed part way and its completed steps were reversed
+    pub rolled_back: bool,
+    /// Directories removed by --prune-empty
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub pruned: Vec<String>,
     pub errors: Vec<String>,
 }
 
@@ -20,21 +43,46 @@ pub struct FileMove {
     pub size: u64,
 }
 
+/// Every folder `categorize_by_type` can return.
+pub const TYPE_CATEGORIES: &[&str] = &[
+    "Images",
+    "Videos",
+    "Audio",
+    "Documents",
+    "Archives",
+    "Code",
+    "Executables",
+    "Fonts",
+    "DiskImages",
+    "Other",
+];
+
+/// Every folder `categorize_by_size` can return.
+pub const SIZE_BUCKETS: &[&str] = &[
+    "Empty",
+    "Tiny (< 1KB)",
+    "Small (1KB-1MB)",
+    "Medium (1MB-100MB)",
+    "Large (100MB-1GB)",
+    "Huge (> 1GB)",
+];
+
+/// Year folders produced by `--by date`.
+pub fn is_year_folder(name: &str) -> bool {
+    name == "Unknown" || (name.len() == 4 && name.bytes().all(|b| b.is_ascii_digit()))
+}
+
 /// Map file extensions to category folders.
-fn categorize_by_type(ext: &str) -> &'static str {
+pub fn categorize_by_type(ext: &str) -> &'static str {
     match ext {
-        "jpg" | "jpeg" | "png" | "gif" | "bmp" | "svg" | "webp" | "ico" | "tiff" | "tif" => {
-            "Images"
-        }
+        "jpg" | "jpeg" | "png" | "gif" | "bmp" | "svg" | "webp" | "ico" | "tiff" | "tif"
+        | "heic" | "avif" => "Images",
         "mp4" | "mkv" | "avi" | "mov" | "wmv" | "flv" | "webm" => "Videos",
         "mp3" | "wav" | "flac" | "aac" | "ogg" | "wma" | "m4a" => "Audio",
         "pdf" | "doc" | "docx" | "xls" | "xlsx" | "ppt" | "pptx" | "odt" | "ods" | "odp"
         | "txt" | "rtf" | "csv" | "md" => "Documents",
         "zip" | "tar" | "gz" | "bz2" | "xz" | "7z" | "rar" | "zst" => "Archives",
-        "rs" | "py" | "js" | "ts" | "go" | "c" | "cpp" | "h" | "hpp" | "java" | "rb" | "php"
-        | "swift" | "kt" | "cs" | "sh" | "bash" | "zsh" | "fish" | "ps1" | "toml" | "yaml"
-        | "yml" | "json" | "xml" | "html" | "css" | "scss" | "less" | "sql" | "r" | "lua"
-        | "vim" | "el" | "ex" | "exs" | "hs" | "ml" | "clj" => "Code",
+        _ if language_for(ext).is_some() => "Code",
         "exe" | "msi" | "dmg" | "app" | "deb" | "rpm" | "appimage" | "bin" => "Executables",
         "ttf" | "otf" | "woff" | "woff2" | "eot" => "Fonts",
         "iso" | "img" | "vmdk" | "vdi" | "qcow2" => "DiskImages",
@@ -42,6 +90,17 @@ fn categorize_by_type(ext: &str) -> &'static str {
     }
 }
 
+/// Categorize by content-detected type. Specific signatures override the
+/// extension; generic ones (zip, plain text) only fill in when the file has
+/// no extension, since a .docx or .rs file is still more precise.
+fn categorize_by_mime(file: &FileInfo) -> &'static str {
+    let ext = file.extension.as_deref().unwrap_or("");
+    match sniff(&file.path) {
+        Some(t) if !t.generic || ext.is_empty() => categorize_by_type(t.ext),
+        _ => categorize_by_type(ext),
+    }
+}
+
 /// Categorize by date (YYYY/MM folder structure).
 fn categorize_by_date(modified: Option<std::time::SystemTime>) -> String {
     match modified {
@@ -54,7 +113,7 @@ fn categorize_by_date(modified: Option<std::time::SystemTime>) -> String {
 }
 
 /// Categorize by size bucket.
-fn categorize_by_size(size: u64) -> &'static str {
+pub fn categorize_by_size(size: u64) -> &'static str {
     if size == 0 {
         "Empty"
     } else if size < 1_000 {
@@ -70,17 +129,9 @@ fn categorize_by_size(size: u64) -> &'static str {
     }
 }
 
-/// Generate a non-colliding path by appending _1, _2, etc.
-fn resolve_collision(dest: &Path, mode: &str) -> PathBuf {
-    if !dest.exists() || mode == "overwrite" {
-        return dest.to_path_buf();
-    }
-
-    if mode == "skip" {
-        return dest.to_path_buf(); // Caller checks exists + skip
-    }
-
-    // mode == "rename"
+/// Generate a non-colliding path by appending _1, _2, etc. `taken` tells
+/// whether a candidate is already used, on disk or earlier in the plan.
+fn resolve_collision(dest: &Path, taken: impl Fn(&Path) -> bool) -> PathBuf {
     let stem = dest.file_stem().and_then(|s| s.to_str()).unwrap_or("file");
     let ext = dest.extension().and_then(|e| e.to_str()).unwrap_or("");
     let parent = dest.parent().unwrap_or(Path::new("."));
@@ -92,7 +143,7 @@ fn resolve_collision(dest: &Path, mode: &str) -> PathBuf {
             format!("{}_{}.{}", stem, i, ext)
         };
         let candidate = parent.join(new_name);
-        if !candidate.exists() {
+        if !taken(&candidate) {
             return candidate;
         }
     }
@@ -100,123 +151,805 @@ fn resolve_collision(dest: &Path, mode: &str) -> PathBuf {
     dest.to_path_buf()
 }
 
-pub fn run_organize(
-    directory: &str,
-    by: &str,
-    dry_run: bool,
+/// Collision modes for files whose destination is already taken.
+const MODES: &[&str] = &["rename", "skip", "overwrite", "dedupe", "newer-wins"];
+
+/// Where a file goes once a taken destination is taken into account.
+enum Placement {
+    /// Place the file at this path
+    At(PathBuf),
+    /// Place the file at the destination, replacing what is there
+    Replace,
+    /// Leave the file where it is
+    Skip,
+    /// The destination already holds the same content
+    Identical,
+}
+
+/// True if `other` has the same size and blake3 hash as `path`.
+fn same_content(path: &Path, size: u64, other: &Path) -> bool {
+    std::fs::metadata(other).is_ok_and(|m| m.len() == size)
+        && hash_file(path, size).is_some_and(|h| hash_file(other, size) == Some(h))
+}
+
+/// Apply the collision mode to a destination held by `occupant`: the file
+/// already there, or the source of a file planned to go there.
+fn place(
+    file: &FileInfo,
+    dest: &Path,
+    occupant: &Path,
     mode: &str,
-    recursive: bool,
-    output: Option<&str>,
-) -> OrganizeResult {
+    taken: impl Fn(&Path) -> bool,
+) -> Placement {
+    match mode {
+        "skip" => Placement::Skip,
+        "overwrite" => Placement::Replace,
+        "dedupe" if same_content(&file.path, file.size, occupant) => Placement::Identical,
+        "newer-wins" => {
+            let mtime = |p: &Path| std::fs::metadata(p).and_then(|m| m.modified()).ok();
+            if mtime(&file.path) > mtime(occupant) {
+                Placement::Replace
+            } else {
+                Placement::Skip
+            }
+        }
+        // dedupe with different content keeps both, like rename
+        _ => Placement::At(resolve_collision(dest, taken)),
+    }
+}
+
+/// Extensions browsers and download tools use while a file is still
+/// being written.
+const PARTIAL_EXTS: &[&str] = &["part", "partial", "crdownload", "download", "opdownload"];
+
+/// Whether `file` is an unfinished download, which organize never touches.
+fn is_partial_download(file: &FileInfo) -> bool {
+    file.extension
+        .as_deref()
+        .is_some_and(|ext| PARTIAL_EXTS.contains(&ext))
+}
+
+/// Whether `name` is a top-level folder the strategy sorts into. Files
+/// already inside one are left out of an in-place run so it doesn't
+/// re-sort what it sorted before.
+fn is_destination_root(
+    by: &str,
+    name: &str,
+    rule_set: Option<&RuleSet>,
+    template: Option<&Template>,
+) -> bool {
+    match by {
+        "type" | "mime" => TYPE_CATEGORIES.contains(&name),
+        "size" => SIZE_BUCKETS.contains(&name),
+        "date" => is_year_folder(name),
+        "rules" => rule_set.is_some_and(|r| r.top_folders().any(|f| f == name)),
+        "template" => template.is_some_and(|t| t.is_top_folder(name)),
+        _ => false,
+    }
+}
+
+/// Canonicalize the longest existing prefix of `path` and append the rest,
+/// so paths that don't exist yet still resolve symlinks above them.
+fn resolve_existing(path: &Path) -> PathBuf {
+    let path = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    let mut existing = path.as_path();
+    let mut rest = Vec::new();
+    loop {
+        if let Ok(canonical) = existing.canonicalize() {
+            return rest.iter().rev().fold(canonical, |p, c| p.join(c));
+        }
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                rest.push(name.to_os_string());
+                existing = parent;
+            }
+            _ => return path,
+        }
+    }
+}
+
+/// Remove directories left empty by the run, walking up from each
+/// directory a file was taken from but never removing `root` itself.
+fn prune_empty_dirs(root: &Path, dirs: HashSet<PathBuf>) -> Vec<String> {
+    let mut dirs: Vec<PathBuf> = dirs.into_iter().collect();
+    // Deepest first, so parents see their children already gone
+    dirs.sort_by_key(|d| std::cmp::Reverse(d.components().count()));
+
+    let mut pruned = Vec::new();
+    for dir in dirs {
+        let mut current = dir.as_path();
+        while current != root && current.starts_with(root) {
+            // remove_dir only succeeds on empty directories
+            if std::fs::remove_dir(current).is_err() {
+                break;
+            }
+            pruned.push(current.display().to_string());
+            match current.parent() {
+                Some(parent) => current = parent,
+                None => break,
+            }
+        }
+    }
+    pruned
+}
+
+/// Ways of placing a file at its destination.
+const ACTIONS: &[&str] = &["move", "copy", "hardlink", "symlink"];
+
+#[cfg(unix)]
+fn symlink_file(from: &Path, to: &Path) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(from, to)
+}
+
+#[cfg(windows)]
+fn symlink_file(from: &Path, to: &Path) -> std::io::Result<()> {
+    std::os::windows::fs::symlink_file(from, to)
+}
+
+/// Place `from` at `to` with the given action. Symlinks point at the
+/// absolute source path so they resolve from any destination.
+fn apply_action(action: &str, from: &Path, to: &Path) -> std::io::Result<()> {
+    match action {
+        "copy" => std::fs::copy(from, to).map(|_| ()),
+        "hardlink" => std::fs::hard_link(from, to),
+        "symlink" => symlink_file(&std::path::absolute(from)?, to),
+        _ => move_path(from, to),
+    }
+}
+
+/// One planned step of an organize run.
+struct Op<'a> {
+    file: &'a FileInfo,
+    dest: PathBuf,
+    /// `dest` already holds this content; the source is only removed
+    duplicate: bool,
+    /// An existing file at `dest` is replaced
+    replace: bool,
+}
+
+/// A problem found before anything was touched, tied to the op that
+/// caused it when there is one.
+struct Problem {
+    op: Option<usize>,
+    message: String,
+}
+
+#[cfg(unix)]
+fn device_id(path: &Path) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    std::fs::metadata(path).ok().map(|m| m.dev())
+}
+
+#[cfg(not(unix))]
+fn device_id(_path: &Path) -> Option<u64> {
+    None
+}
+
+/// The closest ancestor of `path` (or `path` itself) that exists.
+fn nearest_existing(path: &Path) -> &Path {
+    path.ancestors()
+        .find(|p| p.symlink_metadata().is_ok())
+        .unwrap_or(path)
+}
+
+/// Whether files can be created in `dir`. With `probe` a scratch file is
+/// created and removed again, which also catches ACLs and read-only mounts;
+/// otherwise only the permission bits are consulted.
+fn dir_writable(dir: &Path, probe: bool) -> bool {
+    if !probe {
+        return std::fs::metadata(dir).is_ok_and(|m| !m.permissions().readonly());
+    }
+    let scratch = dir.join(format!(".fiq-preflight-{}", std::process::id()));
+    match std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&scratch)
+    {
+        Ok(_) => {
+            let _ = std::fs::remove_file(&scratch);
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+/// Check the whole plan before touching anything: sources must be readable
+/// (and removable when moved), destination folders creatable and writable,
+/// replaced destinations must be files, and every device that receives
+/// copied data needs room for it.
+fn preflight(ops: &[Option<Op<'_>>], action: &str, probe: bool) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    let mut writable: HashMap<PathBuf, bool> = HashMap::new();
+    let mut can_write = |dir: &Path| {
+        *writable
+            .entry(dir.to_path_buf())
+            .or_insert_with(|| dir_writable(dir, probe))
+    };
+    // Bytes that will be copied onto each device, with a path on it
+    let mut needed: HashMap<u64, (PathBuf, u64)> = HashMap::new();
+
+    for (i, op) in ops.iter().enumerate() {
+        let Some(op) = op else { continue };
+        let mut problem = |message: String| {
+            problems.push(Problem {
+                op: Some(i),
+                message,
+            })
+        };
+        let source = &op.file.path;
+
+        if let Err(e) = std::fs::File::open(source) {
+            problem(format!("Cannot read {}: {}", source.display(), e));
+            continue;
+        }
+        if (action == "move" || op.duplicate)
+            && let Some(parent) = source.parent()
+            && !can_write(parent)
+        {
+            problem(format!(
+                "Cannot remove {}: {} is not writable",
+                source.display(),
+                parent.display()
+            ));
+            continue;
+        }
+        if op.duplicate {
+            continue;
+        }
+
+        let dest_dir = op.dest.parent().unwrap_or(Path::new("."));
+        let existing = nearest_existing(dest_dir);
+        if !existing.is_dir() {
+            problem(format!(
+                "Cannot create {}: {} is not a directory",
+                dest_dir.display(),
+                existing.display()
+            ));
+            continue;
+        }
+        if !can_write(existing) {
+            problem(format!("Cannot write to {}", existing.display()));
+            continue;
+        }
+        if op.replace && op.dest.is_dir() {
+            problem(format!(
+                "Cannot replace {}: it is a directory",
+                op.dest.display()
+            ));
+            continue;
+        }
+
+        let copies_data =
+            action == "copy" || (action == "move" && device_id(source) != device_id(existing));
+        if copies_data && let Some(dev) = device_id(existing) {
+            needed.entry(dev).or_insert((existing.to_path_buf(), 0)).1 += op.file.size;
+        }
+    }
+
+    for (path, bytes) in needed.into_values() {
+        if let Ok(free) = fs4::available_space(&path)
+            && free < bytes
+        {
+            problems.push(Problem {
+                op: None,
+                message: format!(
+                    "Not enough space on {}: {} bytes needed, {} available",
+                    path.display(),
+                    bytes,
+                    free
+                ),
+            });
+        }
+    }
+    problems
+}
+
+/// Where a replaced destination is kept until the run succeeds.
+fn backup_path(dest: &Path) -> PathBuf {
+    let name = dest.file_name().and_then(|n| n.to_str()).unwrap_or("file");
+    dest.with_file_name(format!(".{}.fiq-replaced", name))
+}
+
+/// Carry out one planned op. A file being replaced is renamed aside
+/// first so a rollback can put it back; the backup path is returned.
+/// Directories created for the destination are added to `created`, even
+/// when the op then fails.
+fn execute(
+    op: &Op<'_>,
+    action: &str,
+    hash: bool,
+    created: &mut Vec<PathBuf>,
+) -> Result<(JournalEntry, Option<PathBuf>), String> {
+    let source = &op.file.path;
+    let file_hash = if hash {
+        hash_file(source, op.file.size)
+    } else {
+        None
+    };
+    let entry = |action: &str| JournalEntry {
+        timestamp: now_timestamp(),
+        action: action.to_string(),
+        from: source.display().to_string(),
+        to: op.dest.display().to_string(),
+        size: op.file.size,
+        modified: std::fs::metadata(&op.dest).and_then(|m| m.modified()).ok(),
+        hash: file_hash.clone(),
+    };
+
+    if op.duplicate {
+        std::fs::remove_file(source)
+            .map_err(|e| format!("Failed to remove duplicate {}: {}", source.display(), e))?;
+        return Ok((entry("dedupe"), None));
+    }
+
+    if let Some(dest_dir) = op.dest.parent() {
+        created.extend(
+            create_dirs(dest_dir)
+                .map_err(|e| format!("Failed to create {}: {}", dest_dir.display(), e))?,
+        );
+    }
+    let backup = if op.replace && op.dest.symlink_metadata().is_ok() {
+        let backup = backup_path(&op.dest);
+        std::fs::rename(&op.dest, &backup)
+            .map_err(|e| format!("Failed to replace {}: {}", op.dest.display(), e))?;
+        Some(backup)
+    } else {
+        None
+    };
+
+    if let Err(e) = apply_action(action, source, &op.dest) {
+        if let Some(backup) = &backup {
+            let _ = std::fs::rename(backup, &op.dest);
+        }
+        return Err(format!(
+            "Failed to {} {} → {}: {}",
+            action,
+            source.display(),
+            op.dest.display(),
+            e
+        ));
+    }
+    Ok((entry(action), backup))
+}
+
+/// Reverse completed ops newest first and restore the files they replaced.
+/// Returns the journal entries that could not be reverted.
+fn rollback(
+    done: Vec<(JournalEntry, Option<PathBuf>)>,
+    errors: &mut Vec<String>,
+) -> Vec<JournalEntry> {
+    let mut stuck = Vec::new();
+    for (entry, backup) in done.into_iter().rev() {
+        if let Err(e) = revert(&entry) {
+            errors.push(format!("Rollback failed for {}: {}", entry.to, e));
+            stuck.push(entry);
+            continue;
+        }
+        if let Some(backup) = backup
+            && let Err(e) = std::fs::rename(&backup, &entry.to)
+        {
+            errors.push(format!(
+                "Rollback could not restore {} from {}: {}",
+                entry.to,
+                backup.display(),
+                e
+            ));
+        }
+    }
+    // Journal order is execution order
+    stuck.reverse();
+    stuck
+}
+
+pub struct OrganizeParams<'a> {
+    pub directory: &'a str,
+    pub by: &'a str,
+    pub dry_run: bool,
+    pub mode: &'a str,
+    /// move, copy, hardlink or symlink
+    pub action: &'a str,
+    pub recursive: bool,
+    pub output: Option<&'a str>,
+    /// Record blake3 hashes in the undo journal
+    pub hash: bool,
+    /// Rules file for the "rules" strategy (default: ~/.config/fiq/rules.toml)
+    pub rules: Option<&'a str>,
+    /// Destination path template for the "template" strategy
+    pub template: Option<&'a str>,
+    /// Prefer embedded photo/video capture time over mtime
+    pub media_date: bool,
+    /// Remove directories emptied by the run
+    pub prune_empty: bool,
+    /// Skip files that fail preflight or execution instead of aborting and
+    /// rolling back the run
+    pub keep_going: bool,
+}
+
+pub fn run_organize(params: &OrganizeParams<'_>) -> OrganizeResult {
+    let OrganizeParams {
+        directory,
+        by,
+        dry_run,
+        mode,
+        action,
+        recursive,
+        output,
+        hash,
+        rules,
+        template,
+        media_date,
+        prune_empty,
+        keep_going,
+    } = *params;
+    let failed = |error: String| OrganizeResult {
+        total_files: 0,
+        moves: Vec::new(),
+        identical: Vec::new(),
+        action: action.to_string(),
+        dry_run,
+        run_id: None,
+        rolled_back: false,
+        pruned: Vec::new(),
+        errors: vec![error],
+    };
+
+    if !MODES.contains(&mode) {
+        return failed(format!("Unknown collision mode: {}", mode));
+    }
+    if !ACTIONS.contains(&action) {
+        return failed(format!("Unknown action: {}", action));
+    }
+
+    // Passing a rules file or template implies the matching strategy
+    let by = if template.is_some() {
+        "template"
+    } else if rules.is_some() {
+        "rules"
+    } else {
+        by
+    };
+    let rule_set = if by == "rules" {
+        match RuleSet::load(rules) {
+            Ok(r) => Some(r),
+            Err(e) => return failed(e),
+        }
+    } else {
+        None
+    };
+    let template = if by == "template" {
+        let Some(text) = template else {
+            return failed("--by template requires --template".to_string());
+        };
+        match Template::parse(text) {
+            Ok(t) if t.max_capture().is_some() => {
+                return failed(format!(
+                    "Invalid template \"{}\": capture groups need fiq rename --match",
+                    text
+                ));
+            }
+            Ok(t) => Some(t),
+            Err(e) => return failed(format!("Invalid template \"{}\": {}", text, e)),
+        }
+    } else {
+        None
+    };
+
     let dir = Path::new(directory);
     let output_base = output
         .map(PathBuf::from)
         .unwrap_or_else(|| dir.to_path_buf());
-    let files = scan_directory(dir, recursive);
+    if output_base.is_file() {
+        return failed(format!(
+            "Output {} is a file, not a directory",
+            output_base.display()
+        ));
+    }
+    let real_dir = resolve_existing(dir);
+    let real_output = resolve_existing(&output_base);
+
+    // Leave out files the strategy already sorted: everything under an
+    // output folder nested in the source, or the strategy's own top-level
+    // folders when organizing in place
+    let mut files = scan_directory(dir, recursive);
+    files.retain(|f| !is_partial_download(f));
+    match real_output.strip_prefix(&real_dir) {
+        Ok(nested) if nested.as_os_str().is_empty() => files.retain(|f| {
+            let mut components = f.path.strip_prefix(dir).unwrap_or(&f.path).components();
+            let top = components.next().and_then(|c| c.as_os_str().to_str());
+            match (top, components.next()) {
+                (Some(top), Some(_)) => {
+                    !is_destination_root(by, top, rule_set.as_ref(), template.as_ref())
+                }
+                _ => true,
+            }
+        }),
+        Ok(nested) => files.retain(|f| {
+            !f.path
+                .strip_prefix(dir)
+                .is_ok_and(|p| p.starts_with(nested))
+        }),
+        Err(_) => {}
+    }
+    // Counters and collision suffixes follow path order, so dry runs and
+    // real runs plan the same
+    files.sort_by(|a, b| a.path.cmp(&b.path));
     let total_files = files.len();
 
-    let mut moves = Vec::new();
+    // Swap in capture times up front so date, rules and templates all see them
+    if media_date {
+        files.par_iter_mut().for_each(|f| {
+            if let Some(t) = capture_time(&f.path) {
+                f.modified = Some(t);
+            }
+        });
+    }
+
     let mut errors = Vec::new();
+    let mut identical = Vec::new();
 
-    // Track destination counts for dry-run collision simulation
-    let mut dest_counts: HashMap<PathBuf, usize> = HashMap::new();
+    // Plan every file first, so dry runs and real runs resolve collisions
+    // the same way and preflight can see the whole run
+    let mut ops: Vec<Option<Op<'_>>> = Vec::new();
+    // Planned destinations and the op that fills each
+    let mut claimed: HashMap<PathBuf, usize> = HashMap::new();
+    let mut problems: Vec<Problem> = Vec::new();
 
-    for file in &files {
+    for (index, file) in files.iter().enumerate() {
         let ext = file.extension.as_deref().unwrap_or("");
 
+        let file_name = file
+            .path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("unknown");
+
         let category = match by {
             "type" => categorize_by_type(ext).to_string(),
+            "mime" => categorize_by_mime(file).to_string(),
             "date" => categorize_by_date(file.modified),
             "size" => categorize_by_size(file.size).to_string(),
+            "rules" if let Some(ref rule_set) = rule_set => rule_set.categorize(file).to_string(),
+            "template" if let Some(ref template) = template => {
+                let file_hash = if template.needs_hash() {
+                    hash_file(&file.path, file.size)
+                } else {
+                    None
+                };
+                let values = Values {
+                    hash: file_hash.as_deref(),
+                    counter: index + 1,
+                    ..Values::default()
+                };
+                match template.render(file, &values) {
+                    Ok(path) => path.display().to_string(),
+                    Err(e) => {
+                        errors.push(format!("Skipped {}: {}", file.path.display(), e));
+                        continue;
+                    }
+                }
+            }
             _ => {
                 errors.push(format!("Unknown strategy: {}", by));
                 continue;
             }
         };
 
-        let file_name = file
-            .path
-            .file_name()
-            .and_then(|n| n.to_str())
-            .unwrap_or("unknown");
-        let dest_dir = output_base.join(&category);
-        let dest_path = dest_dir.join(file_name);
+        // Templates produce the full relative path, other strategies a folder
+        let dest_path = if template.is_some() {
+            output_base.join(&category)
+        } else {
+            output_base.join(&category).join(file_name)
+        };
+        let dest_dir = dest_path.parent().unwrap_or(&output_base).to_path_buf();
+
+        // A symlinked folder could lead anywhere, including back into the
+        // source tree; refuse destinations that leave the output directory
+        if !resolve_existing(&dest_dir).starts_with(&real_output) {
+            errors.push(format!(
+                "Refusing to place {}: {} resolves outside {}",
+                file.path.display(),
+                dest_dir.display(),
+                output_base.display()
+            ));
+            continue;
+        }
 
         // Skip if source and destination are the same
         if file.path == dest_path {
             continue;
         }
 
-        let final_dest = if dry_run {
-            // Simulate collision handling in dry-run
-            let count = dest_counts.entry(dest_path.clone()).or_insert(0);
-            *count += 1;
-            if *count > 1 && mode == "rename" {
-                let stem = dest_path
-                    .file_stem()
-                    .and_then(|s| s.to_str())
-                    .unwrap_or("file");
-                let ext_str = dest_path.extension().and_then(|e| e.to_str()).unwrap_or("");
-                if ext_str.is_empty() {
-                    dest_dir.join(format!("{}_{}", stem, *count - 1))
-                } else {
-                    dest_dir.join(format!("{}_{}.{}", stem, *count - 1, ext_str))
-                }
-            } else {
-                dest_path
+        let taken = |p: &Path| p.symlink_metadata().is_ok() || claimed.contains_key(p);
+        let earlier = claimed.get(&dest_path).copied();
+        let placement = match earlier {
+            Some(other) => {
+                let occupant = ops[other].as_ref().map_or(&file.path, |op| &op.file.path);
+                place(file, &dest_path, occupant, mode, taken)
             }
-        } else {
-            // Real move
-            if let Err(e) = std::fs::create_dir_all(&dest_dir) {
-                errors.push(format!("Failed to create {}: {}", dest_dir.display(), e));
-                continue;
+            None if dest_path.symlink_metadata().is_ok() => {
+                place(file, &dest_path, &dest_path, mode, taken)
             }
+            None => Placement::At(dest_path.clone()),
+        };
 
-            if dest_path.exists() && mode == "skip" {
+        let (dest, replace) = match placement {
+            Placement::At(path) => (path, false),
+            Placement::Skip => continue,
+            Placement::Replace => match earlier {
+                // Newer-wins within the run: this file displaces the earlier one
+                Some(other) if mode == "newer-wins" => {
+                    ops[other] = None;
+                    let on_disk = dest_path.symlink_metadata().is_ok();
+                    (dest_path, on_disk)
+                }
+                Some(other) => {
+                    let first = ops[other].as_ref().map(|op| op.file.path.display());
+                    problems.push(Problem {
+                        op: None,
+                        message: format!(
+                            "{} and {} would both be written to {}",
+                            first.map(|p| p.to_string()).unwrap_or_default(),
+                            file.path.display(),
+                            dest_path.display()
+                        ),
+                    });
+                    continue;
+                }
+                None => (dest_path, true),
+            },
+            Placement::Identical => {
+                // Nothing to place; a moved source is dropped as a duplicate
+                if action == "move" {
+                    ops.push(Some(Op {
+                        file,
+                        dest: dest_path,
+                        duplicate: true,
+                        replace: false,
+                    }));
+                } else {
+                    identical.push(FileMove {
+                        from: file.path.display().to_string(),
+                        to: dest_path.display().to_string(),
+                        size: file.size,
+                    });
+                }
                 continue;
             }
+        };
 
-            let resolved = resolve_collision(&dest_path, mode);
+        claimed.insert(dest.clone(), ops.len());
+        ops.push(Some(Op {
+            file,
+            dest,
+            duplicate: false,
+            replace,
+        }));
+    }
 
-            if let Err(e) = std::fs::rename(&file.path, &resolved) {
-                // Fall back to copy+delete for cross-device moves
-                if e.kind() == std::io::ErrorKind::CrossesDevices || e.raw_os_error() == Some(18) {
-                    if let Err(e) = std::fs::copy(&file.path, &resolved)
-                        .and_then(|_| std::fs::remove_file(&file.path))
-                    {
-                        errors.push(format!(
-                            "Failed to copy {} → {}: {}",
-                            file.path.display(),
-                            resolved.display(),
-                            e
-                        ));
-                        continue;
-                    }
+    problems.extend(preflight(&ops, action, !dry_run));
+    if !problems.is_empty() && !keep_going && !dry_run {
+        errors.push(format!(
+            "Preflight found {} problem(s); nothing was changed",
+            problems.len()
+        ));
+        errors.extend(problems.into_iter().map(|p| p.message));
+        return OrganizeResult {
+            total_files,
+            moves: Vec::new(),
+            identical: Vec::new(),
+            action: action.to_string(),
+            dry_run,
+            run_id: None,
+            rolled_back: false,
+            pruned: Vec::new(),
+            errors,
+        };
+    }
+    for problem in problems {
+        if let Some(i) = problem.op {
+            ops[i] = None;
+        }
+        errors.push(problem.message);
+    }
+
+    let mut moves = Vec::new();
+    let mut journal = Journal::new();
+    // Completed ops with the backup of any file they replaced
+    let mut done: Vec<(JournalEntry, Option<PathBuf>)> = Vec::new();
+    let mut aborted = false;
+    // Directories files were moved out of, for --prune-empty
+    let mut emptied_dirs: HashSet<PathBuf> = HashSet::new();
+
+    for op in ops.iter().flatten() {
+        let file_move = FileMove {
+            from: op.file.path.display().to_string(),
+            to: op.dest.display().to_string(),
+            size: op.file.size,
+        };
+        if dry_run {
+            if op.duplicate {
+                identical.push(file_move);
+            } else {
+                moves.push(file_move);
+            }
+            continue;
+        }
+
+        let mut created = Vec::new();
+        let outcome = execute(op, action, hash, &mut created);
+        // New folders go in the journal ahead of the file, so undo and
+        // rollback remove them after moving it back
+        for dir in created {
+            let entry = JournalEntry::mkdir(&dir);
+            if let Err(e) = journal.record(&entry) {
+                errors.push(format!("Failed to write undo journal: {}", e));
+            }
+            done.push((entry, None));
+        }
+        match outcome {
+            Ok((entry, backup)) => {
+                if let Err(e) = journal.record(&entry) {
+                    errors.push(format!("Failed to write undo journal: {}", e));
+                }
+                done.push((entry, backup));
+                if (action == "move" || op.duplicate)
+                    && let Some(parent) = op.file.path.parent()
+                {
+                    emptied_dirs.insert(parent.to_path_buf());
+                }
+                if op.duplicate {
+                    identical.push(file_move);
                 } else {
-                    errors.push(format!(
-                        "Failed to move {} → {}: {}",
-                        file.path.display(),
-                        resolved.display(),
-                        e
-                    ));
-                    continue;
+                    moves.push(file_move);
                 }
             }
+            Err(e) => {
+                errors.push(e);
+                if !keep_going {
+                    aborted = true;
+                    break;
+                }
+            }
+        }
+    }
 
-            resolved
-        };
-
-        moves.push(FileMove {
-            from: file.path.display().to_string(),
-            to: final_dest.display().to_string(),
-            size: file.size,
-        });
+    let rolled_back = aborted;
+    let mut run_id = journal.is_written().then(|| journal.run_id().to_string());
+    if rolled_back {
+        let stuck = rollback(done, &mut errors);
+        if let Some(id) = &run_id
+            && let Err(e) = journal::finish_undo(id, &stuck)
+        {
+            errors.push(format!("Failed to update journal {}: {}", id, e));
+        }
+        if stuck.is_empty() {
+            run_id = None;
+            moves.clear();
+            identical.clear();
+        }
+    } else {
+        for backup in done.into_iter().filter_map(|(_, backup)| backup) {
+            if let Err(e) = std::fs::remove_file(&backup) {
+                errors.push(format!("Failed to remove {}: {}", backup.display(), e));
+            }
+        }
     }
 
+    let pruned = if prune_empty && !dry_run && !rolled_back {
+        prune_empty_dirs(dir, emptied_dirs)
+    } else {
+        Vec::new()
+    };
+
     OrganizeResult {
         total_files,
         moves,
+        identical,
+        action: action.to_string(),
         dry_run,
+        run_id,
+        rolled_back,
+        pruned,
         errors,
     }
 }
@@ -0,0 +1,271 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use rayon::prelude::*;
+use regex::Regex;
+use serde::Serialize;
+
+use crate::commands::duplicates::hash_file;
+use crate::commands::organize::FileMove;
+use crate::commands::undo::revert;
+use crate::journal::{self, Journal, JournalEntry, now_timestamp};
+use crate::media_date::capture_time;
+use crate::scanner::scan_directory;
+use crate::template::{Template, Values};
+
+#[derive(Debug, Serialize)]
+pub struct RenameResult {
+    /// Files whose name matched the pattern
+    pub matched: usize,
+    pub renames: Vec<FileMove>,
+    /// Renames that would clash with another file; any conflict stops the
+    /// whole batch
+    pub conflicts: Vec<String>,
+    pub dry_run: bool,
+    /// Journal id for `fiq undo`, set when files were renamed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub run_id: Option<String>,
+    pub errors: Vec<String>,
+}
+
+pub struct RenameParams<'a> {
+    pub directory: &'a str,
+    /// Regex matched against file names
+    pub pattern: &'a str,
+    /// New name template
+    pub to: &'a str,
+    /// Only preview unless false
+    pub dry_run: bool,
+    /// Prefer embedded photo/video capture time over mtime for dates
+    pub media_date: bool,
+    pub recursive: bool,
+}
+
+/// Whether `a` and `b` name the same file, as when only the case of a
+/// name changes on a case-insensitive file system.
+fn same_file(a: &Path, b: &Path) -> bool {
+    let same_name = a.file_name().map(|n| n.to_string_lossy().to_lowercase())
+        == b.file_name().map(|n| n.to_string_lossy().to_lowercase());
+    same_name
+        && match (a.canonicalize(), b.canonicalize()) {
+            (Ok(a), Ok(b)) => a == b,
+            _ => false,
+        }
+}
+
+/// Temporary name used to break a rename cycle such as a swap.
+fn cycle_path(path: &Path) -> PathBuf {
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("file");
+    path.with_file_name(format!(".{}.fiq-rename-{}", name, std::process::id()))
+}
+
+pub fn run_rename(params: &RenameParams<'_>) -> RenameResult {
+    let RenameParams {
+        directory,
+        pattern,
+        to,
+        dry_run,
+        media_date,
+        recursive,
+    } = *params;
+    let mut result = RenameResult {
+        matched: 0,
+        renames: Vec::new(),
+        conflicts: Vec::new(),
+        dry_run,
+        run_id: None,
+        errors: Vec::new(),
+    };
+
+    let regex = match Regex::new(pattern) {
+        Ok(r) => r,
+        Err(e) => {
+            result.errors.push(format!("Invalid pattern: {}", e));
+            return result;
+        }
+    };
+    let template = match Template::parse(to) {
+        Ok(t) => t,
+        Err(e) => {
+            result
+                .errors
+                .push(format!("Invalid template \"{}\": {}", to, e));
+            return result;
+        }
+    };
+    if let Some(group) = template.max_capture()
+        && group >= regex.captures_len()
+    {
+        result.errors.push(format!(
+            "Template uses {{{}}} but the pattern has {} capture group(s)",
+            group,
+            regex.captures_len() - 1
+        ));
+        return result;
+    }
+
+    let mut files = scan_directory(Path::new(directory), recursive);
+    files.retain(|f| {
+        f.path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(|n| regex.is_match(n))
+    });
+    // Counters follow path order, so previews are stable
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    result.matched = files.len();
+
+    if media_date {
+        files.par_iter_mut().for_each(|f| {
+            if let Some(t) = capture_time(&f.path) {
+                f.modified = Some(t);
+            }
+        });
+    }
+
+    // Plan every rename before checking any of them
+    let mut plan: Vec<(PathBuf, PathBuf, u64)> = Vec::new();
+    for (index, file) in files.iter().enumerate() {
+        let name = file.path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+        let Some(captures) = regex.captures(name) else {
+            continue;
+        };
+        let file_hash = if template.needs_hash() {
+            hash_file(&file.path, file.size)
+        } else {
+            None
+        };
+        let values = Values {
+            hash: file_hash.as_deref(),
+            captures: Some(&captures),
+            counter: index + 1,
+        };
+        match template.render_name(file, &values) {
+            Ok(new_name) if new_name != name => {
+                plan.push((
+                    file.path.clone(),
+                    file.path.with_file_name(new_name),
+                    file.size,
+                ));
+            }
+            Ok(_) => {}
+            Err(e) => result
+                .errors
+                .push(format!("Skipped {}: {}", file.path.display(), e)),
+        }
+    }
+
+    // A destination may be another file of the batch that is renamed away
+    // (chains and swaps), but not a file that stays or another destination
+    let sources: HashSet<&Path> = plan.iter().map(|(from, _, _)| from.as_path()).collect();
+    let mut targets: HashMap<&Path, &Path> = HashMap::new();
+    for (from, dest, _) in &plan {
+        if let Some(other) = targets.insert(dest, from) {
+            result.conflicts.push(format!(
+                "{} and {} would both be renamed to {}",
+                other.display(),
+                from.display(),
+                dest.display()
+            ));
+        } else if dest.symlink_metadata().is_ok()
+            && !sources.contains(dest.as_path())
+            && !same_file(from, dest)
+        {
+            result.conflicts.push(format!(
+                "Cannot rename {}: {} already exists",
+                from.display(),
+                dest.display()
+            ));
+        }
+    }
+
+    result.renames = plan
+        .iter()
+        .map(|(from, dest, size)| FileMove {
+            from: from.display().to_string(),
+            to: dest.display().to_string(),
+            size: *size,
+        })
+        .collect();
+    if dry_run || !result.conflicts.is_empty() {
+        return result;
+    }
+
+    // Rename files whose destination is free first; when only cycles are
+    // left, move one file aside to a temporary name to break the cycle.
+    // Every step is journaled, so undo can replay them in reverse.
+    let mut pending: Vec<(PathBuf, PathBuf, u64)> = plan;
+    let mut journal = Journal::new();
+    let mut done: Vec<JournalEntry> = Vec::new();
+    while !pending.is_empty() {
+        let busy: HashSet<&Path> = pending.iter().map(|(from, _, _)| from.as_path()).collect();
+        let next = pending
+            .iter()
+            .position(|(from, dest, _)| !busy.contains(dest.as_path()) || same_file(from, dest));
+        let (from, dest, size) = match next {
+            Some(i) => pending.remove(i),
+            None => {
+                let aside = cycle_path(&pending[0].0);
+                let step = (pending[0].0.clone(), aside.clone(), pending[0].2);
+                pending[0].0 = aside;
+                step
+            }
+        };
+
+        if let Err(e) = std::fs::rename(&from, &dest) {
+            result.errors.push(format!(
+                "Failed to rename {} → {}: {}",
+                from.display(),
+                dest.display(),
+                e
+            ));
+            break;
+        }
+        let entry = JournalEntry {
+            timestamp: now_timestamp(),
+            action: "move".to_string(),
+            from: from.display().to_string(),
+            to: dest.display().to_string(),
+            size,
+            modified: std::fs::metadata(&dest).and_then(|m| m.modified()).ok(),
+            hash: None,
+        };
+        if let Err(e) = journal.record(&entry) {
+            result
+                .errors
+                .push(format!("Failed to write undo journal: {}", e));
+        }
+        done.push(entry);
+    }
+
+    let run_id = journal.is_written().then(|| journal.run_id().to_string());
+    if pending.is_empty() {
+        result.run_id = run_id;
+        return result;
+    }
+
+    // A rename failed: put back what was already renamed
+    let mut stuck = Vec::new();
+    for entry in done.into_iter().rev() {
+        if let Err(e) = revert(&entry) {
+            result
+                .errors
+                .push(format!("Rollback failed for {}: {}", entry.to, e));
+            stuck.push(entry);
+        }
+    }
+    stuck.reverse();
+    if let Some(id) = &run_id
+        && let Err(e) = journal::finish_undo(id, &stuck)
+    {
+        result
+            .errors
+            .push(format!("Failed to update journal {}: {}", id, e));
+    }
+    if stuck.is_empty() {
+        result.renames.clear();
+    } else {
+        result.run_id = run_id;
+    }
+    result
+}
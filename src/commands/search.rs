@@ -6,6 +6,7 @@ use memmap2::Mmap;
 use rayon::prelude::*;
 use serde::Serialize;
 
+use crate::media_date::capture_time;
 use crate::scanner::{FileInfo, scan_directory_filtered, scan_directory_names_only};
 
 const MMAP_THRESHOLD: u64 = 128 * 1024;
@@ -135,6 +136,8 @@ pub struct SearchParams<'a> {
     pub max_size: Option<&'a str>,
     pub newer: Option<&'a str>,
     pub older: Option<&'a str>,
+    /// Compare photo/video capture time instead of mtime where available
+    pub media_date: bool,
     pub recursive: bool,
 }
 
@@ -198,14 +201,22 @@ pub fn run_search_with_cache(params: &SearchParams<'_>, use_memory_cache: bool)
         })
         .filter(|f| {
             // Date filters
+            if newer_time.is_none() && older_time.is_none() {
+                return true;
+            }
+            let modified = if params.media_date {
+                capture_time(&f.path).or(f.modified)
+            } else {
+                f.modified
+            };
             if let Some(newer_t) = newer_time {
-                match f.modified {
+                match modified {
                     Some(mod_time) if mod_time >= newer_t => {}
                     _ => return false,
                 }
             }
             if let Some(older_t) = older_time {
-                match f.modified {
+                match modified {
                     Some(mod_time) if mod_time <= older_t => {}
                     _ => return false,
                 }
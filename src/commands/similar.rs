@@ -0,0 +1,505 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use image::DynamicImage;
+use rayon::prelude::*;
+use serde::Serialize;
+
+use crate::scanner::{FileInfo, scan_directory};
+use crate::sniff::{BINARY_CHECK_LEN, looks_binary};
+
+/// Extensions the image decoder is built with.
+const IMAGE_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "webp", "gif"];
+
+/// Number of bits in a perceptual hash.
+const HASH_BITS: u32 = 64;
+
+/// Words per shingle for text similarity.
+const SHINGLE_WORDS: usize = 3;
+
+/// MinHash signature length, split into LSH bands of `BAND_ROWS` rows.
+/// 32 bands x 4 rows puts the candidate threshold at ~0.42 Jaccard, well
+/// below any sensible `--min-similarity`, so LSH rarely misses a true pair.
+const SIGNATURE_LEN: usize = 128;
+const BAND_ROWS: usize = 4;
+
+/// Text files larger than this are skipped (logs, dumps — not documents).
+const MAX_TEXT_SIZE: u64 = 16 * 1024 * 1024;
+
+#[derive(Debug, Serialize)]
+pub struct SimilarResult {
+    pub total_files_scanned: usize,
+    pub files_compared: usize,
+    pub similar_groups: Vec<SimilarGroup>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SimilarGroup {
+    pub kind: &'static str,
+    pub total_size: u64,
+    pub files: Vec<SimilarFile>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SimilarFile {
+    pub path: String,
+    pub size: u64,
+    /// Similarity to the first file in the group, from 0.0 to 1.0: hash bit
+    /// agreement for images, estimated Jaccard similarity for text
+    pub similarity: f64,
+}
+
+/// Perceptual hash used to compare images.
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum HashAlgorithm {
+    /// Difference hash: fast, robust to resizing and re-encoding
+    Dhash,
+    /// DCT hash: slower, more robust to brightness and color changes
+    Phash,
+}
+
+pub struct SimilarParams<'a> {
+    pub directory: &'a str,
+    pub images: bool,
+    pub algorithm: HashAlgorithm,
+    pub threshold: u32,
+    pub text: bool,
+    pub min_similarity: f64,
+    pub min_size: u64,
+    pub recursive: bool,
+}
+
+/// Difference hash: compare each pixel of a 9x8 grayscale thumbnail with its
+/// right-hand neighbour. Robust to resizing and re-encoding, cheap to compute.
+fn dhash(img: &DynamicImage) -> u64 {
+    let thumb = img.thumbnail_exact(9, 8).to_luma8();
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            hash <<= 1;
+            if thumb.get_pixel(x, y)[0] < thumb.get_pixel(x + 1, y)[0] {
+                hash |= 1;
+            }
+        }
+    }
+    hash
+}
+
+/// DCT hash: keep the 8x8 lowest frequencies of a 32x32 grayscale thumbnail
+/// and set a bit for each coefficient above the median, except the DC term,
+/// so the top bit is always clear. Slower than dHash but tolerates
+/// brightness/contrast changes better.
+fn phash(img: &DynamicImage) -> u64 {
+    const N: usize = 32;
+    let thumb = img.thumbnail_exact(N as u32, N as u32).to_luma8();
+    let pixels: Vec<f64> = thumb.pixels().map(|p| p[0] as f64).collect();
+
+    // Separable DCT-II: rows first, then columns, only the 8 lowest frequencies
+    let cos_table: Vec<f64> = (0..8)
+        .flat_map(|u| {
+            (0..N)
+                .map(move |x| (std::f64::consts::PI / N as f64 * (x as f64 + 0.5) * u as f64).cos())
+        })
+        .collect();
+
+    let mut rows = [[0.0f64; 8]; N];
+    for (y, row) in rows.iter_mut().enumerate() {
+        for (u, coeff) in row.iter_mut().enumerate() {
+            *coeff = (0..N)
+                .map(|x| pixels[y * N + x] * cos_table[u * N + x])
+                .sum();
+        }
+    }
+
+    let mut coeffs = [0.0f64; 64];
+    for v in 0..8 {
+        for u in 0..8 {
+            coeffs[v * 8 + u] = (0..N).map(|y| rows[y][u] * cos_table[v * N + y]).sum();
+        }
+    }
+
+    // The DC term only reflects average brightness — leave it out of the
+    // median and the hash
+    let ac = &coeffs[1..];
+    let mut sorted: Vec<f64> = ac.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let median = sorted[sorted.len() / 2];
+
+    ac.iter()
+        .fold(0u64, |hash, &c| (hash << 1) | (c > median) as u64)
+}
+
+/// Decode an image and compute its perceptual hash.
+fn hash_image(path: &Path, algorithm: HashAlgorithm) -> Option<u64> {
+    let img = image::ImageReader::open(path)
+        .ok()?
+        .with_guessed_format()
+        .ok()?
+        .decode()
+        .ok()?;
+    match algorithm {
+        HashAlgorithm::Dhash => Some(dhash(&img)),
+        HashAlgorithm::Phash => Some(phash(&img)),
+    }
+}
+
+/// Disjoint-set forest used to merge pairwise matches into groups.
+struct UnionFind {
+    parent: Vec<usize>,
+}
+
+impl UnionFind {
+    fn new(n: usize) -> Self {
+        UnionFind {
+            parent: (0..n).collect(),
+        }
+    }
+
+    fn find(&mut self, mut i: usize) -> usize {
+        while self.parent[i] != i {
+            self.parent[i] = self.parent[self.parent[i]];
+            i = self.parent[i];
+        }
+        i
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let (ra, rb) = (self.find(a), self.find(b));
+        if ra != rb {
+            self.parent[rb] = ra;
+        }
+    }
+}
+
+/// splitmix64 finalizer — a fast, well-distributed 64-bit mixer.
+fn mix64(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+    x ^ (x >> 31)
+}
+
+/// FNV-1a over a word sequence, separating words so "ab c" != "a bc".
+fn hash_words(words: &[&str]) -> u64 {
+    let mut hash = 0xcbf29ce484222325u64;
+    for word in words {
+        for &b in word.as_bytes().iter().chain(b" ") {
+            hash ^= b as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    }
+    hash
+}
+
+/// Compute a MinHash signature over lowercased word shingles.
+/// Returns None for files that are binary, unreadable, or have no words.
+fn minhash_file(path: &Path) -> Option<[u64; SIGNATURE_LEN]> {
+    // Sniff the start before reading the rest, so binaries cost one read
+    let mut file = File::open(path).ok()?;
+    let mut data = Vec::new();
+    (&mut file)
+        .take(BINARY_CHECK_LEN as u64)
+        .read_to_end(&mut data)
+        .ok()?;
+    if looks_binary(&data) {
+        return None;
+    }
+    file.read_to_end(&mut data).ok()?;
+    let text = String::from_utf8_lossy(&data).to_lowercase();
+    let words: Vec<&str> = text.split_whitespace().collect();
+    if words.is_empty() {
+        return None;
+    }
+
+    let mut signature = [u64::MAX; SIGNATURE_LEN];
+    for shingle in words.windows(SHINGLE_WORDS.min(words.len())) {
+        let base = hash_words(shingle);
+        for (i, slot) in signature.iter_mut().enumerate() {
+            // Each slot is an independent permutation seeded by its index
+            let h = mix64(base ^ mix64(i as u64 + 1));
+            if h < *slot {
+                *slot = h;
+            }
+        }
+    }
+    Some(signature)
+}
+
+/// Fraction of matching signature slots — an unbiased Jaccard estimate.
+fn estimate_jaccard(a: &[u64; SIGNATURE_LEN], b: &[u64; SIGNATURE_LEN]) -> f64 {
+    let same = a.iter().zip(b).filter(|(x, y)| x == y).count();
+    same as f64 / SIGNATURE_LEN as f64
+}
+
+/// Cluster text files using LSH banding over MinHash signatures, keeping
+/// only candidate pairs whose estimated Jaccard similarity is high enough.
+fn group_texts(
+    signed: &[(&FileInfo, [u64; SIGNATURE_LEN])],
+    min_similarity: f64,
+) -> Vec<SimilarGroup> {
+    let mut buckets: HashMap<(usize, u64), Vec<usize>> = HashMap::new();
+    for (i, (_, sig)) in signed.iter().enumerate() {
+        for (band, rows) in sig.chunks(BAND_ROWS).enumerate() {
+            let key = rows.iter().fold(0u64, |acc, &r| mix64(acc ^ r));
+            buckets.entry((band, key)).or_default().push(i);
+        }
+    }
+
+    let mut candidates: Vec<(usize, usize)> = buckets
+        .into_values()
+        .filter(|b| b.len() > 1)
+        .flat_map(|b| {
+            (0..b.len())
+                .flat_map(|x| (x + 1..b.len()).map(move |y| (x, y)))
+                .map(|(x, y)| (b[x], b[y]))
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    candidates.sort_unstable();
+    candidates.dedup();
+
+    let pairs: Vec<(usize, usize)> = candidates
+        .into_par_iter()
+        .filter(|&(a, b)| estimate_jaccard(&signed[a].1, &signed[b].1) >= min_similarity)
+        .collect();
+
+    let mut uf = UnionFind::new(signed.len());
+    for (a, b) in pairs {
+        uf.union(a, b);
+    }
+
+    collect_groups(&mut uf, signed.len())
+        .into_iter()
+        .map(|mut m| {
+            m.sort_by_key(|&i| std::cmp::Reverse(signed[i].0.size));
+            let reference = &signed[m[0]].1;
+            let files: Vec<SimilarFile> = m
+                .iter()
+                .map(|&i| {
+                    let (file, sig) = &signed[i];
+                    SimilarFile {
+                        path: file.path.display().to_string(),
+                        size: file.size,
+                        similarity: estimate_jaccard(sig, reference),
+                    }
+                })
+                .collect();
+            SimilarGroup {
+                kind: "text",
+                total_size: files.iter().map(|f| f.size).sum(),
+                files,
+            }
+        })
+        .collect()
+}
+
+/// Members of each union-find set with two or more elements.
+fn collect_groups(uf: &mut UnionFind, n: usize) -> Vec<Vec<usize>> {
+    let mut members: HashMap<usize, Vec<usize>> = HashMap::new();
+    for i in 0..n {
+        members.entry(uf.find(i)).or_default().push(i);
+    }
+    members.into_values().filter(|m| m.len() > 1).collect()
+}
+
+/// Group images whose hashes are within `threshold` bits of each other.
+fn group_images(hashed: &[(&FileInfo, u64)], threshold: u32) -> Vec<SimilarGroup> {
+    // All-pairs comparison is a popcount per pair — fast enough for tens of
+    // thousands of images, and exact (no missed pairs as with bucketing)
+    let pairs: Vec<(usize, usize)> = (0..hashed.len())
+        .into_par_iter()
+        .flat_map_iter(|i| {
+            (i + 1..hashed.len())
+                .filter(move |&j| (hashed[i].1 ^ hashed[j].1).count_ones() <= threshold)
+                .map(move |j| (i, j))
+        })
+        .collect();
+
+    let mut uf = UnionFind::new(hashed.len());
+    for (a, b) in pairs {
+        uf.union(a, b);
+    }
+
+    collect_groups(&mut uf, hashed.len())
+        .into_iter()
+        .map(|mut m| {
+            // Largest file first — usually the original, highest-quality copy
+            m.sort_by_key(|&i| std::cmp::Reverse(hashed[i].0.size));
+            let reference = hashed[m[0]].1;
+            let files: Vec<SimilarFile> = m
+                .iter()
+                .map(|&i| {
+                    let (file, hash) = hashed[i];
+                    let distance = (hash ^ reference).count_ones();
+                    SimilarFile {
+                        path: file.path.display().to_string(),
+                        size: file.size,
+                        similarity: 1.0 - distance as f64 / HASH_BITS as f64,
+                    }
+                })
+                .collect();
+            SimilarGroup {
+                kind: "image",
+                total_size: files.iter().map(|f| f.size).sum(),
+                files,
+            }
+        })
+        .collect()
+}
+
+pub fn run_similar(params: &SimilarParams<'_>) -> SimilarResult {
+    let dir = Path::new(params.directory);
+    let files = scan_directory(dir, params.recursive);
+    let total_files_scanned = files.len();
+
+    let mut similar_groups = Vec::new();
+    let mut files_compared = 0;
+
+    if params.images {
+        let candidates: Vec<&FileInfo> = files
+            .iter()
+            .filter(|f| f.size >= params.min_size)
+            .filter(|f| {
+                f.extension
+                    .as_deref()
+                    .is_some_and(|e| IMAGE_EXTENSIONS.contains(&e))
+            })
+            .collect();
+
+        // Decoding dominates — hash in parallel, silently skipping unreadable images
+        let hashed: Vec<(&FileInfo, u64)> = candidates
+            .par_iter()
+            .filter_map(|f| Some((*f, hash_image(&f.path, params.algorithm)?)))
+            .collect();
+
+        files_compared += hashed.len();
+        similar_groups.extend(group_images(&hashed, params.threshold));
+    }
+
+    if params.text {
+        let candidates: Vec<&FileInfo> = files
+            .iter()
+            .filter(|f| f.size >= params.min_size && f.size <= MAX_TEXT_SIZE)
+            .filter(|f| {
+                // Images are compared perceptually, never as text
+                !f.extension
+                    .as_deref()
+                    .is_some_and(|e| IMAGE_EXTENSIONS.contains(&e))
+            })
+            .collect();
+
+        let signed: Vec<(&FileInfo, [u64; SIGNATURE_LEN])> = candidates
+            .par_iter()
+            .filter_map(|f| Some((*f, minhash_file(&f.path)?)))
+            .collect();
+
+        files_compared += signed.len();
+        similar_groups.extend(group_texts(&signed, params.min_similarity));
+    }
+
+    similar_groups.sort_by_key(|g| std::cmp::Reverse(g.total_size));
+
+    SimilarResult {
+        total_files_scanned,
+        files_compared,
+        similar_groups,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageFormat, Rgb, RgbImage};
+
+    fn gradient(width: u32, height: u32, invert: bool) -> DynamicImage {
+        let img = RgbImage::from_fn(width, height, |x, y| {
+            let v = ((x * 255 / width + y * 64 / height) % 256) as u8;
+            let v = if invert { 255 - v } else { v };
+            Rgb([v, v / 2, 255 - v])
+        });
+        DynamicImage::ImageRgb8(img)
+    }
+
+    #[test]
+    fn test_resized_image_hashes_close() {
+        let original = gradient(256, 192, false);
+        let resized = original.resize_exact(97, 73, image::imageops::FilterType::Triangle);
+        let other = gradient(256, 192, true);
+
+        assert!((dhash(&original) ^ dhash(&resized)).count_ones() <= 4);
+        assert!((phash(&original) ^ phash(&resized)).count_ones() <= 4);
+        assert!((dhash(&original) ^ dhash(&other)).count_ones() > 16);
+        // No bit for the DC term
+        assert_eq!(phash(&original) >> 63, 0);
+    }
+
+    #[test]
+    fn test_run_similar_groups_reencoded_images() {
+        let dir = tempfile::tempdir().unwrap();
+        let original = gradient(320, 240, false);
+        original
+            .save_with_format(dir.path().join("photo.png"), ImageFormat::Png)
+            .unwrap();
+        original
+            .resize_exact(160, 120, image::imageops::FilterType::Triangle)
+            .save_with_format(dir.path().join("photo_small.jpg"), ImageFormat::Jpeg)
+            .unwrap();
+        gradient(320, 240, true)
+            .save_with_format(dir.path().join("different.png"), ImageFormat::Png)
+            .unwrap();
+        std::fs::write(dir.path().join("broken.jpg"), b"not an image").unwrap();
+
+        let result = run_similar(&SimilarParams {
+            directory: dir.path().to_str().unwrap(),
+            images: true,
+            algorithm: HashAlgorithm::Dhash,
+            threshold: 10,
+            text: false,
+            min_similarity: 0.8,
+            min_size: 1,
+            recursive: true,
+        });
+
+        assert_eq!(result.total_files_scanned, 4);
+        assert_eq!(result.files_compared, 3);
+        assert_eq!(result.similar_groups.len(), 1);
+        let group = &result.similar_groups[0];
+        assert_eq!(group.files.len(), 2);
+        assert!(group.files[0].path.ends_with("photo.png"));
+        assert_eq!(group.files[0].similarity, 1.0);
+    }
+
+    #[test]
+    fn test_run_similar_clusters_edited_text() {
+        let dir = tempfile::tempdir().unwrap();
+        let base: String = (0..200)
+            .map(|i| format!("setting_{} = value_{}\n", i, i * 7))
+            .collect();
+        let edited = base.replace("setting_42 = value_294", "setting_42 = changed");
+        let unrelated: String = (0..200).map(|i| format!("line {} of notes\n", i)).collect();
+
+        std::fs::write(dir.path().join("app.conf"), &base).unwrap();
+        std::fs::write(dir.path().join("app.conf.bak"), &edited).unwrap();
+        std::fs::write(dir.path().join("notes.txt"), &unrelated).unwrap();
+        std::fs::write(dir.path().join("blob.bin"), [0u8, 1, 2, 3, 0, 5]).unwrap();
+
+        let result = run_similar(&SimilarParams {
+            directory: dir.path().to_str().unwrap(),
+            images: false,
+            algorithm: HashAlgorithm::Dhash,
+            threshold: 10,
+            text: true,
+            min_similarity: 0.8,
+            min_size: 1,
+            recursive: true,
+        });
+
+        assert_eq!(result.files_compared, 3);
+        assert_eq!(result.similar_groups.len(), 1);
+        let group = &result.similar_groups[0];
+        assert_eq!(group.kind, "text");
+        assert_eq!(group.files.len(), 2);
+        assert!(group.files[1].similarity >= 0.8);
+    }
+}
@@ -1,16 +1,59 @@
 use std::collections::HashMap;
 use std::path::Path;
+use std::time::{Duration, SystemTime};
 
+use rayon::prelude::*;
 use serde::Serialize;
 
-use crate::scanner::scan_directory;
+use crate::languages::{LineCounts, count_file, language_for};
+use crate::scanner::{FileInfo, scan_directory};
+use crate::snapshot::{Snapshot, SnapshotDiff};
+use crate::sniff::sniff;
 
 #[derive(Debug, Serialize)]
 pub struct StatsResult {
     pub total_files: usize,
+    /// Sizes are allocated disk space rather than apparent size
+    pub disk_usage: bool,
     pub total_size: u64,
+    /// Files with less space allocated than their length (sparse or
+    /// compressed)
+    pub sparse_files: usize,
     pub by_extension: Vec<ExtensionStats>,
+    /// Breakdown by content-detected type, when requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub by_type: Option<Vec<TypeStats>>,
+    /// Lines of code per language, most code first, when requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub languages: Option<Vec<LanguageStats>>,
+    /// Breakdown by owning user, when requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub by_owner: Option<Vec<OwnerStats>>,
+    /// Breakdown by owning group, when requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub by_group: Option<Vec<OwnerStats>>,
+    /// Permission problems, when requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audit: Option<PermissionAudit>,
     pub largest_files: Vec<FileEntry>,
+    /// Size and age distributions over all files, when requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub histograms: Option<Histograms>,
+    /// Cumulative size per directory down to `--depth`, when requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tree: Option<DirNode>,
+    /// Directories holding the most data directly (not counting
+    /// subdirectories), when the tree is requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub largest_dirs: Option<Vec<DirEntry>>,
+    /// Id of the snapshot saved by this run
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snapshot: Option<String>,
+    /// Changes since the snapshot compared against
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub changes: Option<SnapshotDiff>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -18,59 +61,583 @@ pub struct ExtensionStats {
     pub extension: String,
     pub count: usize,
     pub total_size: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub histograms: Option<Histograms>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TypeStats {
+    pub mime: String,
+    pub count: usize,
+    pub total_size: u64,
+}
+
+/// Lines of code per language, as counted by `--code`.
+#[derive(Debug, Serialize)]
+pub struct LanguageStats {
+    pub language: String,
+    pub files: usize,
+    pub code: usize,
+    pub comments: usize,
+    pub blanks: usize,
+    pub total_size: u64,
+}
+
+/// Files and bytes owned by one user or group.
+#[derive(Debug, Serialize)]
+pub struct OwnerStats {
+    pub name: String,
+    pub id: u32,
+    pub count: usize,
+    pub total_size: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AuditEntry {
+    pub path: String,
+    pub size: u64,
+    pub owner: String,
+    /// Permission bits in octal, e.g. "4755"
+    pub mode: String,
+}
+
+/// Files matching one audit check: all of them are counted, the largest
+/// `top_n` are listed.
+#[derive(Debug, Serialize)]
+pub struct AuditFindings {
+    pub count: usize,
+    pub total_size: u64,
+    pub files: Vec<AuditEntry>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PermissionAudit {
+    pub current_user: String,
+    /// Files anyone may write to
+    pub world_writable: AuditFindings,
+    /// Executables with the setuid or setgid bit
+    pub setuid: AuditFindings,
+    /// Files owned by someone other than the current user
+    pub not_owned: AuditFindings,
 }
 
 #[derive(Debug, Serialize)]
 pub struct FileEntry {
     pub path: String,
     pub size: u64,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub sparse: bool,
+}
+
+/// Upper bounds of the log-scale size buckets; sizes at or above the last
+/// bound go in a final open bucket.
+const SIZE_BOUNDS: &[(u64, &str)] = &[
+    (1, "0 B"),
+    (10, "1-9 B"),
+    (100, "10-99 B"),
+    (1_000, "100 B-1 KB"),
+    (10_000, "1-10 KB"),
+    (100_000, "10-100 KB"),
+    (1_000_000, "100 KB-1 MB"),
+    (10_000_000, "1-10 MB"),
+    (100_000_000, "10-100 MB"),
+    (1_000_000_000, "100 MB-1 GB"),
+    (10_000_000_000, "1-10 GB"),
+    (100_000_000_000, "10-100 GB"),
+];
+const SIZE_OVERFLOW: &str = "100 GB+";
+
+const DAY: u64 = 86_400;
+
+/// Upper bounds of the age buckets, by time since last modification.
+const AGE_BOUNDS: &[(u64, &str)] = &[
+    (DAY, "< 1 day"),
+    (7 * DAY, "< 1 week"),
+    (30 * DAY, "< 1 month"),
+    (365 * DAY, "< 1 year"),
+];
+const AGE_OVERFLOW: &str = "older";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Bucket {
+    pub label: &'static str,
+    pub count: usize,
+    pub size: u64,
+}
+
+/// File count and bytes per size bucket and per age bucket. Every bucket
+/// is present, in ascending order, so arrays line up across extensions.
+#[derive(Debug, Clone, Serialize)]
+pub struct Histograms {
+    pub size: Vec<Bucket>,
+    pub age: Vec<Bucket>,
+}
+
+impl Histograms {
+    fn new() -> Self {
+        let buckets = |bounds: &[(u64, &'static str)], overflow| {
+            bounds
+                .iter()
+                .map(|&(_, label)| label)
+                .chain([overflow])
+                .map(|label| Bucket {
+                    label,
+                    count: 0,
+                    size: 0,
+                })
+                .collect()
+        };
+        Histograms {
+            size: buckets(SIZE_BOUNDS, SIZE_OVERFLOW),
+            age: buckets(AGE_BOUNDS, AGE_OVERFLOW),
+        }
+    }
+
+    /// Count a file of `size` bytes. Files without an mtime are left out
+    /// of the age histogram; ones dated in the future count as new.
+    fn add(&mut self, size: u64, modified: Option<SystemTime>, now: SystemTime) {
+        let index = |bounds: &[(u64, &str)], value: u64| {
+            bounds
+                .iter()
+                .position(|&(bound, _)| value < bound)
+                .unwrap_or(bounds.len())
+        };
+        let bucket = &mut self.size[index(SIZE_BOUNDS, size)];
+        bucket.count += 1;
+        bucket.size += size;
+
+        if let Some(modified) = modified {
+            let age = now.duration_since(modified).unwrap_or(Duration::ZERO);
+            let bucket = &mut self.age[index(AGE_BOUNDS, age.as_secs())];
+            bucket.count += 1;
+            bucket.size += size;
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct DirNode {
+    pub path: String,
+    /// Total size of all files below this directory
+    pub size: u64,
+    pub files: usize,
+    /// Subdirectories, largest first
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<DirNode>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DirEntry {
+    pub path: String,
+    /// Size of the files directly inside
+    pub size: u64,
+    pub files: usize,
+}
+
+pub struct StatsParams<'a> {
+    pub directory: &'a str,
+    /// Number of largest files (and directories) to list
+    pub top_n: usize,
+    pub recursive: bool,
+    /// Break down by content-detected type
+    pub by_type: bool,
+    /// Count code, comment and blank lines per language
+    pub code: bool,
+    /// Break down by `owner` or `group`
+    pub by: Option<&'a str>,
+    /// Look for world-writable files, setuid executables and files owned
+    /// by other users
+    pub audit: bool,
+    /// Build the per-directory tree
+    pub tree: bool,
+    /// Levels of the tree below the scanned directory
+    pub depth: usize,
+    /// Count allocated disk space instead of apparent size
+    pub disk_usage: bool,
+    /// Size and age histograms, overall and per extension
+    pub histograms: bool,
+    /// Save a snapshot of the tree for later comparison
+    pub save_snapshot: bool,
+    /// Snapshot to compare against: an id, a file, or `last`
+    pub compare: Option<&'a str>,
 }
 
-pub fn run_stats(directory: &str, top_n: usize, recursive: bool) -> StatsResult {
+/// Sum file sizes into every directory between each file and `root`, and
+/// build the tree from `root` down to `depth` levels.
+fn dir_tree(
+    root: &Path,
+    files: &[FileInfo],
+    depth: usize,
+    top_n: usize,
+    disk_usage: bool,
+) -> (DirNode, Vec<DirEntry>) {
+    let mut total: HashMap<&Path, (u64, usize)> = HashMap::new();
+    let mut direct: HashMap<&Path, (u64, usize)> = HashMap::new();
+    for file in files {
+        let Some(parent) = file.path.parent() else {
+            continue;
+        };
+        let own = direct.entry(parent).or_insert((0, 0));
+        let size = file.size_in(disk_usage);
+        own.0 += size;
+        own.1 += 1;
+        for dir in parent.ancestors() {
+            let entry = total.entry(dir).or_insert((0, 0));
+            entry.0 += size;
+            entry.1 += 1;
+            if dir == root {
+                break;
+            }
+        }
+    }
+
+    let mut children: HashMap<&Path, Vec<&Path>> = HashMap::new();
+    for &dir in total.keys() {
+        if dir != root
+            && let Some(parent) = dir.parent()
+        {
+            children.entry(parent).or_default().push(dir);
+        }
+    }
+
+    fn build(
+        dir: &Path,
+        level: usize,
+        depth: usize,
+        total: &HashMap<&Path, (u64, usize)>,
+        children: &HashMap<&Path, Vec<&Path>>,
+    ) -> DirNode {
+        let (size, files) = total.get(dir).copied().unwrap_or((0, 0));
+        let mut nodes: Vec<DirNode> = if level < depth {
+            children
+                .get(dir)
+                .map(|c| {
+                    c.iter()
+                        .map(|child| build(child, level + 1, depth, total, children))
+                        .collect()
+                })
+                .unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+        nodes.sort_by_key(|n| std::cmp::Reverse(n.size));
+        DirNode {
+            path: dir.display().to_string(),
+            size,
+            files,
+            children: nodes,
+        }
+    }
+    let tree = build(root, 0, depth, &total, &children);
+
+    let mut largest: Vec<DirEntry> = direct
+        .into_iter()
+        .map(|(dir, (size, files))| DirEntry {
+            path: dir.display().to_string(),
+            size,
+            files,
+        })
+        .collect();
+    largest.sort_by_key(|d| std::cmp::Reverse(d.size));
+    largest.truncate(top_n);
+    (tree, largest)
+}
+
+/// Count lines of every source file in parallel and sum them per
+/// language. Binary files with a source extension are skipped.
+fn count_languages(files: &[FileInfo], disk_usage: bool) -> Vec<LanguageStats> {
+    let counted: Vec<(&str, LineCounts, u64)> = files
+        .par_iter()
+        .filter_map(|f| {
+            let lang = language_for(f.extension.as_deref()?)?;
+            let counts = count_file(&f.path, lang)?;
+            Some((lang.name, counts, f.size_in(disk_usage)))
+        })
+        .collect();
+
+    let mut lang_map: HashMap<&str, LanguageStats> = HashMap::new();
+    for (name, counts, size) in counted {
+        let entry = lang_map.entry(name).or_insert_with(|| LanguageStats {
+            language: name.to_string(),
+            files: 0,
+            code: 0,
+            comments: 0,
+            blanks: 0,
+            total_size: 0,
+        });
+        entry.files += 1;
+        entry.code += counts.code;
+        entry.comments += counts.comments;
+        entry.blanks += counts.blanks;
+        entry.total_size += size;
+    }
+    let mut languages: Vec<LanguageStats> = lang_map.into_values().collect();
+    languages.sort_by(|a, b| {
+        b.code
+            .cmp(&a.code)
+            .then_with(|| a.language.cmp(&b.language))
+    });
+    languages
+}
+
+#[cfg(unix)]
+fn user_name(uid: u32) -> String {
+    uzers::get_user_by_uid(uid)
+        .map(|u| u.name().to_string_lossy().into_owned())
+        .unwrap_or_else(|| uid.to_string())
+}
+
+#[cfg(unix)]
+fn group_name(gid: u32) -> String {
+    uzers::get_group_by_gid(gid)
+        .map(|g| g.name().to_string_lossy().into_owned())
+        .unwrap_or_else(|| gid.to_string())
+}
+
+#[cfg(unix)]
+fn current_uid() -> u32 {
+    uzers::get_current_uid()
+}
+
+#[cfg(not(unix))]
+fn user_name(uid: u32) -> String {
+    uid.to_string()
+}
+
+#[cfg(not(unix))]
+fn group_name(gid: u32) -> String {
+    gid.to_string()
+}
+
+#[cfg(not(unix))]
+fn current_uid() -> u32 {
+    0
+}
+
+/// Group files by owner or group id, largest total first.
+fn by_id(
+    files: &[FileInfo],
+    id_of: fn(&FileInfo) -> u32,
+    name_of: fn(u32) -> String,
+    disk_usage: bool,
+) -> Vec<OwnerStats> {
+    let mut id_map: HashMap<u32, (usize, u64)> = HashMap::new();
+    for file in files {
+        let entry = id_map.entry(id_of(file)).or_insert((0, 0));
+        entry.0 += 1;
+        entry.1 += file.size_in(disk_usage);
+    }
+    let mut owners: Vec<OwnerStats> = id_map
+        .into_iter()
+        .map(|(id, (count, total_size))| OwnerStats {
+            name: name_of(id),
+            id,
+            count,
+            total_size,
+        })
+        .collect();
+    owners.sort_by_key(|o| std::cmp::Reverse(o.total_size));
+    owners
+}
+
+/// Check permissions of `files`, which are sorted largest first.
+fn permission_audit(files: &[FileInfo], top_n: usize, disk_usage: bool) -> PermissionAudit {
+    let me = current_uid();
+    let mut names: HashMap<u32, String> = HashMap::new();
+    let mut entry = |f: &FileInfo| AuditEntry {
+        path: f.path.display().to_string(),
+        size: f.size_in(disk_usage),
+        owner: names
+            .entry(f.uid)
+            .or_insert_with(|| user_name(f.uid))
+            .clone(),
+        mode: format!("{:04o}", f.mode),
+    };
+
+    // `files` is sorted largest first
+    let mut findings = |check: &dyn Fn(&FileInfo) -> bool| {
+        let matching: Vec<&FileInfo> = files.iter().filter(|f| check(f)).collect();
+        AuditFindings {
+            count: matching.len(),
+            total_size: matching.iter().map(|f| f.size_in(disk_usage)).sum(),
+            files: matching.into_iter().take(top_n).map(&mut entry).collect(),
+        }
+    };
+
+    PermissionAudit {
+        current_user: user_name(me),
+        world_writable: findings(&|f| f.mode & 0o002 != 0),
+        setuid: findings(&|f| f.mode & 0o6000 != 0 && f.mode & 0o111 != 0),
+        not_owned: findings(&|f| f.uid != me),
+    }
+}
+
+pub fn run_stats(params: &StatsParams<'_>) -> StatsResult {
+    let StatsParams {
+        directory,
+        top_n,
+        recursive,
+        by_type,
+        code,
+        by,
+        audit,
+        tree,
+        depth,
+        disk_usage,
+        histograms,
+        save_snapshot,
+        compare,
+    } = *params;
     let dir = Path::new(directory);
     let mut files = scan_directory(dir, recursive);
 
     let total_files = files.len();
-    let total_size: u64 = files.iter().map(|f| f.size).sum();
+    let total_size: u64 = files.iter().map(|f| f.size_in(disk_usage)).sum();
+    let sparse_files = files.iter().filter(|f| f.is_sparse()).count();
 
     // Group by extension
-    let mut ext_map: HashMap<String, (usize, u64)> = HashMap::new();
+    let now = SystemTime::now();
+    let mut overall = histograms.then(Histograms::new);
+    let mut ext_map: HashMap<String, (usize, u64, Option<Histograms>)> = HashMap::new();
     for file in &files {
         let ext = file
             .extension
             .clone()
             .unwrap_or_else(|| "(no ext)".to_string());
-        let entry = ext_map.entry(ext).or_insert((0, 0));
+        let size = file.size_in(disk_usage);
+        let entry = ext_map
+            .entry(ext)
+            .or_insert_with(|| (0, 0, histograms.then(Histograms::new)));
         entry.0 += 1;
-        entry.1 += file.size;
+        entry.1 += size;
+        if let Some(h) = &mut entry.2 {
+            h.add(size, file.modified, now);
+        }
+        if let Some(h) = &mut overall {
+            h.add(size, file.modified, now);
+        }
     }
 
     let mut by_extension: Vec<ExtensionStats> = ext_map
         .into_iter()
-        .map(|(extension, (count, total_size))| ExtensionStats {
-            extension,
-            count,
-            total_size,
-        })
+        .map(
+            |(extension, (count, total_size, histograms))| ExtensionStats {
+                extension,
+                count,
+                total_size,
+                histograms,
+            },
+        )
         .collect();
-    by_extension.sort_by(|a, b| b.total_size.cmp(&a.total_size));
+    by_extension.sort_by_key(|e| std::cmp::Reverse(e.total_size));
+
+    // Group by sniffed content type — reads the head of every file
+    let by_type = by_type.then(|| {
+        let mimes: Vec<&str> = files
+            .par_iter()
+            .map(|f| match sniff(&f.path) {
+                Some(t) => t.mime,
+                None if f.size == 0 => "inode/x-empty",
+                None => "application/octet-stream",
+            })
+            .collect();
+        let mut type_map: HashMap<&str, (usize, u64)> = HashMap::new();
+        for (file, mime) in files.iter().zip(mimes) {
+            let entry = type_map.entry(mime).or_insert((0, 0));
+            entry.0 += 1;
+            entry.1 += file.size_in(disk_usage);
+        }
+        let mut by_type: Vec<TypeStats> = type_map
+            .into_iter()
+            .map(|(mime, (count, total_size))| TypeStats {
+                mime: mime.to_string(),
+                count,
+                total_size,
+            })
+            .collect();
+        by_type.sort_by_key(|t| std::cmp::Reverse(t.total_size));
+        by_type
+    });
+
+    let (tree, largest_dirs) = if tree {
+        let (tree, largest) = dir_tree(dir, &files, depth, top_n, disk_usage);
+        (Some(tree), Some(largest))
+    } else {
+        (None, None)
+    };
+
+    let languages = code.then(|| count_languages(&files, disk_usage));
+
+    let mut errors = Vec::new();
+    let (by_owner, by_group) = match by {
+        None => (None, None),
+        Some("owner") => (Some(by_id(&files, |f| f.uid, user_name, disk_usage)), None),
+        Some("group") => (None, Some(by_id(&files, |f| f.gid, group_name, disk_usage))),
+        Some(other) => {
+            errors.push(format!(
+                "Unknown breakdown: {} (expected owner or group)",
+                other
+            ));
+            (None, None)
+        }
+    };
 
     // Largest files — sort in-place, no intermediate Vec<&FileInfo>
-    files.sort_unstable_by(|a, b| b.size.cmp(&a.size));
+    files.sort_unstable_by_key(|f| std::cmp::Reverse(f.size_in(disk_usage)));
+
+    let audit = audit.then(|| permission_audit(&files, top_n, disk_usage));
 
     let largest_files: Vec<FileEntry> = files
         .iter()
         .take(top_n)
         .map(|f| FileEntry {
             path: f.path.display().to_string(),
-            size: f.size,
+            size: f.size_in(disk_usage),
+            sparse: f.is_sparse(),
         })
         .collect();
 
+    // Compare before saving, so `last` is the previous snapshot
+    let mut snapshot = None;
+    let mut changes = None;
+    if save_snapshot || compare.is_some() {
+        let mut current = Snapshot::take(dir, &files, disk_usage);
+        if let Some(which) = compare {
+            match Snapshot::load(dir, which) {
+                // Measure the way the earlier snapshot did
+                Ok(old) if old.disk_usage != disk_usage => {
+                    let remeasured = Snapshot::take(dir, &files, old.disk_usage);
+                    changes = Some(remeasured.diff_from(&old, depth, top_n));
+                }
+                Ok(old) => changes = Some(current.diff_from(&old, depth, top_n)),
+                Err(e) => errors.push(e),
+            }
+        }
+        if save_snapshot {
+            match current.save() {
+                Ok(()) => snapshot = Some(current.id),
+                Err(e) => errors.push(format!("Failed to save snapshot: {}", e)),
+            }
+        }
+    }
+
     StatsResult {
         total_files,
+        disk_usage,
         total_size,
+        sparse_files,
         by_extension,
+        by_type,
+        languages,
+        by_owner,
+        by_group,
+        audit,
         largest_files,
+        histograms: overall,
+        tree,
+        largest_dirs,
+        snapshot,
+        changes,
+        errors,
     }
 }
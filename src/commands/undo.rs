@@ -0,0 +1,204 @@
+use std::io::ErrorKind;
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::commands::duplicates::hash_file;
+use crate::commands::organize::FileMove;
+use crate::journal::{self, JournalEntry};
+use crate::transfer::move_path;
+
+#[derive(Debug, Serialize)]
+pub struct UndoResult {
+    pub run_id: String,
+    pub restored: Vec<FileMove>,
+    /// Copies and links created by the run that were deleted
+    pub removed: Vec<String>,
+    /// Directories created by the run that were removed again
+    pub removed_dirs: Vec<String>,
+    pub dry_run: bool,
+    pub errors: Vec<String>,
+}
+
+/// Check that the file at `entry.to` is still what the run put there.
+/// Trashed entries may be directories or broken symlinks, so they are
+/// checked without following links.
+fn changed_since(entry: &JournalEntry) -> Option<String> {
+    if entry.action == "mkdir" {
+        return None;
+    }
+    let to = Path::new(&entry.to);
+    let meta = if entry.action == "trash" {
+        std::fs::symlink_metadata(to)
+    } else {
+        std::fs::metadata(to)
+    };
+    let meta = match meta {
+        Ok(m) => m,
+        Err(_) => return Some("no longer exists".to_string()),
+    };
+    if meta.len() != entry.size {
+        return Some("size changed".to_string());
+    }
+    if entry.modified.is_some() && meta.modified().ok() != entry.modified {
+        return Some("modified".to_string());
+    }
+    if let Some(ref expected) = entry.hash
+        && hash_file(to, meta.len()).as_ref() != Some(expected)
+    {
+        return Some("content changed".to_string());
+    }
+    None
+}
+
+/// Reverse one journaled operation, without checking whether that is safe:
+/// moved and trashed files go back, dropped duplicates are copied back from
+/// the kept file, and created copies and links are deleted. Created
+/// directories are removed unless something else has been put in them.
+pub fn revert(entry: &JournalEntry) -> std::io::Result<()> {
+    let from = Path::new(&entry.from);
+    let to = Path::new(&entry.to);
+    match entry.action.as_str() {
+        "mkdir" => match std::fs::remove_dir(to) {
+            Err(e) if matches!(e.kind(), ErrorKind::DirectoryNotEmpty | ErrorKind::NotFound) => {
+                Ok(())
+            }
+            removed => removed,
+        },
+        "move" | "trash" | "dedupe" => {
+            if let Some(parent) = from.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            if entry.action != "dedupe" {
+                move_path(to, from)
+            } else {
+                std::fs::copy(to, from).map(|_| ())
+            }
+        }
+        _ => std::fs::remove_file(to),
+    }
+}
+
+/// Reverse an organize run, newest operation first. Moved files go back to
+/// where they were, duplicates dropped by `dedupe` are copied back, and
+/// copies, links and now empty directories the run created are deleted.
+///
+/// Files changed since the run are left alone unless `force` is set, and
+/// files whose original location is occupied again are never overwritten.
+/// A copy whose original has since disappeared is kept, even with `force`.
+/// Entries that could not be undone stay in the journal for a retry.
+pub fn run_undo(run_id: Option<&str>, dry_run: bool, force: bool) -> UndoResult {
+    let mut result = UndoResult {
+        run_id: String::new(),
+        restored: Vec::new(),
+        removed: Vec::new(),
+        removed_dirs: Vec::new(),
+        dry_run,
+        errors: Vec::new(),
+    };
+
+    let run_id = match run_id {
+        Some(id) => id.to_string(),
+        None => match journal::list_runs().pop() {
+            Some(id) => id,
+            None => {
+                result.errors.push("No organize runs to undo".to_string());
+                return result;
+            }
+        },
+    };
+    result.run_id = run_id.clone();
+    if !journal::is_valid_run_id(&run_id) {
+        result.errors.push(format!("Invalid run id: {}", run_id));
+        return result;
+    }
+
+    let entries = match journal::load_run(&run_id) {
+        Ok(e) => e,
+        Err(e) => {
+            result
+                .errors
+                .push(format!("Failed to read journal {}: {}", run_id, e));
+            return result;
+        }
+    };
+
+    let mut remaining = Vec::new();
+    for entry in entries.iter().rev() {
+        let from = Path::new(&entry.from);
+
+        if !force && let Some(reason) = changed_since(entry) {
+            result
+                .errors
+                .push(format!("Skipped {}: {} since organize", entry.to, reason));
+            remaining.push(entry.clone());
+            continue;
+        }
+
+        let problem = match entry.action.as_str() {
+            "move" | "dedupe" if from.exists() => Some(format!(
+                "Skipped {}: original location {} is occupied",
+                entry.to, entry.from
+            )),
+            "trash" if from.symlink_metadata().is_ok() => Some(format!(
+                "Skipped {}: original location {} is occupied",
+                entry.to, entry.from
+            )),
+            // The original never left; deleting the copy must not lose it
+            "copy" | "hardlink" if !from.exists() => Some(format!(
+                "Kept {}: original {} no longer exists",
+                entry.to, entry.from
+            )),
+            _ => None,
+        };
+        if let Some(problem) = problem {
+            result.errors.push(problem);
+            remaining.push(entry.clone());
+            continue;
+        }
+
+        if !dry_run && let Err(e) = revert(entry) {
+            result.errors.push(format!(
+                "Failed to undo {} → {}: {}",
+                entry.from, entry.to, e
+            ));
+            remaining.push(entry.clone());
+            continue;
+        }
+
+        match entry.action.as_str() {
+            "move" | "trash" | "dedupe" => result.restored.push(FileMove {
+                from: entry.to.clone(),
+                to: entry.from.clone(),
+                size: entry.size,
+            }),
+            // Whether it is empty is only known once the files are back
+            "mkdir" if !dry_run => {
+                if Path::new(&entry.to).exists() {
+                    remaining.push(entry.clone());
+                } else {
+                    result.removed_dirs.push(entry.to.clone());
+                }
+            }
+            "mkdir" => {}
+            _ => result.removed.push(entry.to.clone()),
+        }
+    }
+
+    if !dry_run {
+        // Journal order is execution order; keep it that way for retries
+        remaining.reverse();
+        // A directory still in use is only worth a retry along with the
+        // files that kept it from being removed
+        if remaining.iter().all(|e| e.action == "mkdir") {
+            remaining.clear();
+        }
+        if let Err(e) = journal::finish_undo(&run_id, &remaining) {
+            result
+                .errors
+                .push(format!("Failed to update journal {}: {}", run_id, e));
+        }
+    }
+
+    result
+}
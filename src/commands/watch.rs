@@ -0,0 +1,64 @@
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+use notify::{EventKind, RecursiveMode, Watcher};
+
+use crate::commands::organize::{OrganizeParams, OrganizeResult, run_organize};
+
+/// How often the loop wakes up to check for a shutdown request.
+const POLL: Duration = Duration::from_millis(250);
+
+/// Organize the directory once, then again whenever it has been quiet for
+/// `settle` after a change, until SIGINT or SIGTERM. Each pass is its own
+/// journaled run and is handed to `report`. Watch errors are logged and
+/// followed by a pass. A pass in progress always finishes before exiting.
+pub fn watch_organize(
+    params: &OrganizeParams<'_>,
+    settle: Duration,
+    mut report: impl FnMut(OrganizeResult),
+) -> Result<(), String> {
+    let stop = Arc::new(AtomicBool::new(false));
+    let flag = Arc::clone(&stop);
+    ctrlc::set_handler(move || flag.store(true, Ordering::SeqCst))
+        .map_err(|e| format!("Failed to install signal handler: {}", e))?;
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher =
+        notify::recommended_watcher(tx).map_err(|e| format!("Failed to start watcher: {}", e))?;
+    let mode = if params.recursive {
+        RecursiveMode::Recursive
+    } else {
+        RecursiveMode::NonRecursive
+    };
+    watcher
+        .watch(Path::new(params.directory), mode)
+        .map_err(|e| format!("Failed to watch {}: {}", params.directory, e))?;
+
+    report(run_organize(params));
+
+    // Time of the latest change not yet organized
+    let mut last_change: Option<Instant> = None;
+    while !stop.load(Ordering::SeqCst) {
+        match rx.recv_timeout(POLL) {
+            Ok(Ok(event)) if !matches!(event.kind, EventKind::Access(_)) => {
+                last_change = Some(Instant::now());
+            }
+            Ok(Ok(_)) | Err(RecvTimeoutError::Timeout) => {}
+            // Events may have been lost, e.g. on a queue overflow; every
+            // pass scans the whole directory, so just schedule one
+            Ok(Err(e)) => {
+                eprintln!("Watch error: {}; rescanning", e);
+                last_change = Some(Instant::now());
+            }
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+        if last_change.is_some_and(|t| t.elapsed() >= settle) {
+            last_change = None;
+            report(run_organize(params));
+        }
+    }
+    Ok(())
+}
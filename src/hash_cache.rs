@@ -0,0 +1,117 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+
+/// A persistent memo of blake3 hashes for files under a root directory.
+///
+/// Entries are keyed by path and remember the size and mtime they were
+/// hashed at, so a lookup is only trusted while the file is unchanged.
+/// A cache is `complete` when every file under the root was hashed in
+/// one pass — only then can "which files have hash X?" be answered
+/// without walking the tree.
+#[derive(Serialize, Deserialize)]
+pub struct HashCache {
+    /// Root directory this cache covers
+    pub root: PathBuf,
+    /// When the last full pass over `root` started
+    pub built_at: SystemTime,
+    /// Whether every file under `root` has an entry
+    pub complete: bool,
+    entries: HashMap<PathBuf, CachedHash>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedHash {
+    size: u64,
+    modified: Option<SystemTime>,
+    hash: String,
+}
+
+impl HashCache {
+    pub fn new(root: &Path) -> Self {
+        HashCache {
+            root: root.to_path_buf(),
+            built_at: SystemTime::now(),
+            complete: false,
+            entries: HashMap::new(),
+        }
+    }
+
+    /// Cached hash for a file, if it was hashed at this exact size and mtime.
+    pub fn get(&self, path: &Path, size: u64, modified: Option<SystemTime>) -> Option<&str> {
+        let entry = self.entries.get(path)?;
+        (entry.size == size && entry.modified == modified && modified.is_some())
+            .then_some(entry.hash.as_str())
+    }
+
+    pub fn insert(&mut self, path: PathBuf, size: u64, modified: Option<SystemTime>, hash: String) {
+        self.entries.insert(
+            path,
+            CachedHash {
+                size,
+                modified,
+                hash,
+            },
+        );
+    }
+
+    /// All cached files with the given hash, re-checked against the
+    /// filesystem: entries whose file changed or vanished are dropped.
+    pub fn lookup(&self, hash: &str) -> Vec<(PathBuf, u64)> {
+        self.entries
+            .iter()
+            .filter(|(_, e)| e.hash == hash)
+            .filter(|(path, e)| {
+                std::fs::metadata(path)
+                    .is_ok_and(|m| m.len() == e.size && m.modified().ok() == e.modified)
+            })
+            .map(|(path, e)| (path.clone(), e.size))
+            .collect()
+    }
+
+    /// Same freshness rule as the trigram index: 1-hour TTL.
+    pub fn is_fresh(&self) -> bool {
+        const MAX_AGE: std::time::Duration = std::time::Duration::from_secs(3600);
+        match SystemTime::now().duration_since(self.built_at) {
+            Ok(age) => age < MAX_AGE,
+            Err(_) => true,
+        }
+    }
+
+    /// Cache directory: ~/.cache/fiq/
+    fn cache_dir() -> Option<PathBuf> {
+        dirs::cache_dir().map(|d| d.join("fiq"))
+    }
+
+    /// Deterministic cache key from root path.
+    fn cache_key(root: &Path) -> String {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+        let mut hasher = DefaultHasher::new();
+        root.hash(&mut hasher);
+        format!("{:016x}.hashes", hasher.finish())
+    }
+
+    /// Save the cache to disk.
+    pub fn save_to_cache(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let dir = Self::cache_dir().ok_or("no cache dir")?;
+        std::fs::create_dir_all(&dir)?;
+        let path = dir.join(Self::cache_key(&self.root));
+        let bytes = bincode::serialize(self)?;
+        std::fs::write(path, bytes)?;
+        Ok(())
+    }
+
+    /// Load the cached hashes for a root. Stale caches are still returned —
+    /// individual entries are validated by size and mtime — but callers
+    /// should check `is_fresh()` before trusting `complete`.
+    pub fn load_cached(root: &Path) -> Option<Self> {
+        let dir = Self::cache_dir()?;
+        let path = dir.join(Self::cache_key(root));
+        let bytes = std::fs::read(path).ok()?;
+        let cache: Self = bincode::deserialize(&bytes).ok()?;
+        (cache.root == root).then_some(cache)
+    }
+}
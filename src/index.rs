@@ -265,16 +265,16 @@ mod tests {
     #[test]
     fn test_extract_trigrams_star_test_js() {
         let tris = extract_trigrams_from_glob("*.test.js");
-        assert!(tris.contains(&[b'.', b't', b'e']));
-        assert!(tris.contains(&[b'.', b'j', b's']));
+        assert!(tris.contains(b".te"));
+        assert!(tris.contains(b".js"));
         assert!(tris.len() >= 5);
     }
 
     #[test]
     fn test_extract_trigrams_foo_star_bar() {
         let tris = extract_trigrams_from_glob("foo*bar");
-        assert!(tris.contains(&[b'f', b'o', b'o']));
-        assert!(tris.contains(&[b'b', b'a', b'r']));
+        assert!(tris.contains(b"foo"));
+        assert!(tris.contains(b"bar"));
     }
 
     #[test]
@@ -0,0 +1,228 @@
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+
+/// Suffix given to journals whose run has been fully undone.
+const UNDONE_SUFFIX: &str = ".undone";
+
+/// One file operation performed by an organize run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub timestamp: String,
+    pub action: String,
+    pub from: String,
+    pub to: String,
+    pub size: u64,
+    /// mtime of the file at its destination, for change detection on undo
+    pub modified: Option<SystemTime>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hash: Option<String>,
+}
+
+impl JournalEntry {
+    /// A directory the run created; undo removes it again once empty.
+    pub fn mkdir(dir: &Path) -> Self {
+        let path = dir.display().to_string();
+        JournalEntry {
+            timestamp: now_timestamp(),
+            action: "mkdir".to_string(),
+            from: path.clone(),
+            to: path,
+            size: 0,
+            modified: None,
+            hash: None,
+        }
+    }
+}
+
+/// Append-only record of a single organize run, stored as JSON Lines in
+/// the state directory. The file is created on the first entry, so runs
+/// that change nothing leave no journal behind.
+pub struct Journal {
+    run_id: String,
+    file: Option<File>,
+}
+
+/// State directory: $FIQ_STATE_DIR, or ~/.local/state/fiq/ (Linux),
+/// falling back to the platform's local data dir elsewhere.
+pub fn state_dir() -> Option<PathBuf> {
+    if let Ok(dir) = std::env::var("FIQ_STATE_DIR")
+        && !dir.is_empty()
+    {
+        return Some(PathBuf::from(dir));
+    }
+    dirs::state_dir()
+        .or_else(dirs::data_local_dir)
+        .map(|d| d.join("fiq"))
+}
+
+fn journal_dir() -> Option<PathBuf> {
+    state_dir().map(|d| d.join("journal"))
+}
+
+/// Whether `run_id` has the form `Journal::new` gives ids, e.g.
+/// `20240512-093015-120`. Anything else, such as `../x`, could name a
+/// file outside the journal directory.
+pub fn is_valid_run_id(run_id: &str) -> bool {
+    !run_id.is_empty() && run_id.chars().all(|c| c.is_ascii_digit() || c == '-')
+}
+
+fn journal_path(run_id: &str) -> std::io::Result<PathBuf> {
+    if !is_valid_run_id(run_id) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("invalid run id: {}", run_id),
+        ));
+    }
+    journal_dir()
+        .map(|d| d.join(format!("{}.jsonl", run_id)))
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "no state directory"))
+}
+
+/// Create `<dir>/<id><ext>` under an id no other run has taken: `base`,
+/// or `base-1`, `base-2`, ... when runs start in the same millisecond.
+pub fn create_unique(dir: &Path, base: &str, ext: &str) -> std::io::Result<(String, File)> {
+    let mut n = 0;
+    loop {
+        let id = if n == 0 {
+            base.to_string()
+        } else {
+            format!("{}-{}", base, n)
+        };
+        match OpenOptions::new()
+            .append(true)
+            .create_new(true)
+            .open(dir.join(format!("{}{}", id, ext)))
+        {
+            Ok(file) => return Ok((id, file)),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => n += 1,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Current local time formatted for journal entries.
+pub fn now_timestamp() -> String {
+    chrono::Local::now().to_rfc3339()
+}
+
+impl Journal {
+    /// Start a new run. Run ids sort chronologically.
+    pub fn new() -> Self {
+        let run_id = chrono::Local::now().format("%Y%m%d-%H%M%S-%3f").to_string();
+        Journal { run_id, file: None }
+    }
+
+    /// The run's id. It is only final once the journal is open.
+    pub fn run_id(&self) -> &str {
+        &self.run_id
+    }
+
+    /// True once the journal file has been created.
+    pub fn is_written(&self) -> bool {
+        self.file.is_some()
+    }
+
+    /// Create the journal file, claiming the run id; a run that started in
+    /// the same millisecond gets a suffixed id. `record` opens the journal
+    /// itself, so this is only needed to use the id before the first entry.
+    pub fn open(&mut self) -> std::io::Result<()> {
+        if self.file.is_some() {
+            return Ok(());
+        }
+        let dir = journal_dir().ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::NotFound, "no state directory")
+        })?;
+        std::fs::create_dir_all(&dir)?;
+        let (run_id, file) = create_unique(&dir, &self.run_id, ".jsonl")?;
+        self.run_id = run_id;
+        self.file = Some(file);
+        Ok(())
+    }
+
+    /// Append an entry, flushing immediately so a crash mid-run still
+    /// leaves an accurate journal of what was done.
+    pub fn record(&mut self, entry: &JournalEntry) -> std::io::Result<()> {
+        self.open()?;
+        let file = self.file.as_mut().unwrap();
+        let line = serde_json::to_string(entry)?;
+        writeln!(file, "{}", line)?;
+        file.flush()
+    }
+}
+
+/// Load every entry of a run, in the order they were performed.
+pub fn load_run(run_id: &str) -> std::io::Result<Vec<JournalEntry>> {
+    let path = journal_path(run_id)?;
+    let reader = BufReader::new(File::open(path)?);
+    let mut entries = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        entries.push(serde_json::from_str(&line)?);
+    }
+    Ok(entries)
+}
+
+/// Replace a run's journal with the entries that still need undoing, or
+/// mark it undone when none remain.
+pub fn finish_undo(run_id: &str, remaining: &[JournalEntry]) -> std::io::Result<()> {
+    let path = journal_path(run_id)?;
+    if remaining.is_empty() {
+        let mut undone = path.clone().into_os_string();
+        undone.push(UNDONE_SUFFIX);
+        return std::fs::rename(&path, undone);
+    }
+    let mut text = String::new();
+    for entry in remaining {
+        text.push_str(&serde_json::to_string(entry)?);
+        text.push('\n');
+    }
+    std::fs::write(path, text)
+}
+
+/// Run ids that still have something to undo, oldest first.
+pub fn list_runs() -> Vec<String> {
+    let Some(dir) = journal_dir() else {
+        return Vec::new();
+    };
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut runs: Vec<String> = entries
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            e.file_name()
+                .to_str()
+                .and_then(|n| n.strip_suffix(".jsonl"))
+                .map(String::from)
+        })
+        .collect();
+    runs.sort();
+    runs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_create_unique_suffixes_taken_ids() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = "20240512-093015-120";
+        let ids: Vec<String> = (0..3)
+            .map(|_| create_unique(dir.path(), base, ".jsonl").unwrap().0)
+            .collect();
+        assert_eq!(
+            ids,
+            [base, "20240512-093015-120-1", "20240512-093015-120-2"]
+        );
+        assert!(ids.iter().all(|id| is_valid_run_id(id)));
+        assert!(!is_valid_run_id("../20240512-093015-120"));
+    }
+}
@@ -0,0 +1,224 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use crate::sniff::{BINARY_CHECK_LEN, looks_binary};
+
+/// Source files larger than this are not counted: generated code and data
+/// dumps with a source extension, not code anyone wrote.
+const MAX_SOURCE_SIZE: u64 = 16 * 1024 * 1024;
+
+/// A programming, markup or configuration language recognized by
+/// extension, with the comment syntax used to count its lines.
+#[derive(Debug)]
+pub struct Language {
+    pub name: &'static str,
+    pub extensions: &'static [&'static str],
+    pub line_comments: &'static [&'static str],
+    pub block_comment: Option<(&'static str, &'static str)>,
+}
+
+const C_BLOCK: Option<(&str, &str)> = Some(("/*", "*/"));
+const HTML_BLOCK: Option<(&str, &str)> = Some(("<!--", "-->"));
+
+const fn lang(
+    name: &'static str,
+    extensions: &'static [&'static str],
+    line_comments: &'static [&'static str],
+    block_comment: Option<(&'static str, &'static str)>,
+) -> Language {
+    Language {
+        name,
+        extensions,
+        line_comments,
+        block_comment,
+    }
+}
+
+/// Every language fiq knows; their extensions make up the "Code" category.
+pub const LANGUAGES: &[Language] = &[
+    lang("Rust", &["rs"], &["//"], C_BLOCK),
+    lang(
+        "Python",
+        &["py", "pyi", "pyw"],
+        &["#"],
+        Some(("\"\"\"", "\"\"\"")),
+    ),
+    lang("JavaScript", &["js", "mjs", "cjs", "jsx"], &["//"], C_BLOCK),
+    lang("TypeScript", &["ts", "tsx", "mts", "cts"], &["//"], C_BLOCK),
+    lang("Go", &["go"], &["//"], C_BLOCK),
+    lang("C", &["c", "h"], &["//"], C_BLOCK),
+    lang(
+        "C++",
+        &["cpp", "cc", "cxx", "hpp", "hh", "hxx"],
+        &["//"],
+        C_BLOCK,
+    ),
+    lang("Objective-C", &["m", "mm"], &["//"], C_BLOCK),
+    lang("C#", &["cs"], &["//"], C_BLOCK),
+    lang("Java", &["java"], &["//"], C_BLOCK),
+    lang("Kotlin", &["kt", "kts"], &["//"], C_BLOCK),
+    lang("Scala", &["scala", "sc"], &["//"], C_BLOCK),
+    lang("Groovy", &["groovy", "gradle"], &["//"], C_BLOCK),
+    lang("Swift", &["swift"], &["//"], C_BLOCK),
+    lang("Dart", &["dart"], &["//"], C_BLOCK),
+    lang("Zig", &["zig"], &["//"], None),
+    lang("PHP", &["php"], &["//", "#"], C_BLOCK),
+    lang("Ruby", &["rb"], &["#"], Some(("=begin", "=end"))),
+    lang("Perl", &["pl", "pm"], &["#"], None),
+    lang("Shell", &["sh", "bash", "zsh", "fish", "ksh"], &["#"], None),
+    lang("PowerShell", &["ps1", "psm1"], &["#"], Some(("<#", "#>"))),
+    lang("Lua", &["lua"], &["--"], Some(("--[[", "]]"))),
+    lang("R", &["r"], &["#"], None),
+    lang("Julia", &["jl"], &["#"], Some(("#=", "=#"))),
+    lang("SQL", &["sql"], &["--"], C_BLOCK),
+    lang("Haskell", &["hs"], &["--"], Some(("{-", "-}"))),
+    lang("OCaml", &["ml", "mli"], &[], Some(("(*", "*)"))),
+    lang("Clojure", &["clj", "cljs", "cljc", "edn"], &[";"], None),
+    lang("Emacs Lisp", &["el"], &[";"], None),
+    lang("Elixir", &["ex", "exs"], &["#"], None),
+    lang("Erlang", &["erl", "hrl"], &["%"], None),
+    lang("Vim script", &["vim"], &["\""], None),
+    lang("Nix", &["nix"], &["#"], C_BLOCK),
+    lang("Terraform", &["tf"], &["#", "//"], C_BLOCK),
+    lang("Protobuf", &["proto"], &["//"], C_BLOCK),
+    lang("HTML", &["html", "htm"], &[], HTML_BLOCK),
+    lang("XML", &["xml"], &[], HTML_BLOCK),
+    lang("Vue", &["vue"], &["//"], HTML_BLOCK),
+    lang("Svelte", &["svelte"], &["//"], HTML_BLOCK),
+    lang("CSS", &["css"], &[], C_BLOCK),
+    lang("SCSS", &["scss"], &["//"], C_BLOCK),
+    lang("Less", &["less"], &["//"], C_BLOCK),
+    lang("JSON", &["json"], &[], None),
+    lang("TOML", &["toml"], &["#"], None),
+    lang("YAML", &["yaml", "yml"], &["#"], None),
+];
+
+/// The language for a lowercase file extension.
+pub fn language_for(ext: &str) -> Option<&'static Language> {
+    LANGUAGES.iter().find(|l| l.extensions.contains(&ext))
+}
+
+/// Line counts of one file or a whole language.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct LineCounts {
+    pub code: usize,
+    pub comments: usize,
+    pub blanks: usize,
+}
+
+/// Count code, comment and blank lines. A line with any code on it is a
+/// code line, even with a trailing comment. String literals are not
+/// parsed, so a comment marker inside a string can throw off the count
+/// for the rest of a block comment.
+pub fn count_lines(text: &str, lang: &Language) -> LineCounts {
+    let mut counts = LineCounts::default();
+    // End delimiter of the block comment we are in
+    let mut in_block: Option<&str> = None;
+
+    for line in text.lines() {
+        let mut rest = line.trim();
+        if rest.is_empty() {
+            counts.blanks += 1;
+            continue;
+        }
+
+        let mut code = false;
+        loop {
+            if let Some(end) = in_block {
+                match rest.find(end) {
+                    Some(i) => {
+                        rest = rest[i + end.len()..].trim_start();
+                        in_block = None;
+                    }
+                    None => break,
+                }
+            }
+            if rest.is_empty() {
+                break;
+            }
+            // Block first: Lua's `--[[` also starts with its line comment
+            if let Some((start, end)) = lang.block_comment
+                && rest.starts_with(start)
+            {
+                rest = &rest[start.len()..];
+                in_block = Some(end);
+                continue;
+            }
+            if lang.line_comments.iter().any(|c| rest.starts_with(c)) {
+                break;
+            }
+            code = true;
+            match lang.block_comment.and_then(|(start, _)| rest.find(start)) {
+                Some(i) => rest = &rest[i..],
+                None => break,
+            }
+        }
+
+        if code {
+            counts.code += 1;
+        } else {
+            counts.comments += 1;
+        }
+    }
+    counts
+}
+
+/// Count the lines of a source file. None for unreadable or binary files
+/// and files over `MAX_SOURCE_SIZE`.
+pub fn count_file(path: &Path, lang: &Language) -> Option<LineCounts> {
+    let mut file = File::open(path).ok()?;
+    if file.metadata().ok()?.len() > MAX_SOURCE_SIZE {
+        return None;
+    }
+    // Sniff the start before reading the rest, so binaries cost one read
+    let mut data = Vec::new();
+    (&mut file)
+        .take(BINARY_CHECK_LEN as u64)
+        .read_to_end(&mut data)
+        .ok()?;
+    if looks_binary(&data) {
+        return None;
+    }
+    file.read_to_end(&mut data).ok()?;
+    Some(count_lines(&String::from_utf8_lossy(&data), lang))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counts(code: usize, comments: usize, blanks: usize) -> LineCounts {
+        LineCounts {
+            code,
+            comments,
+            blanks,
+        }
+    }
+
+    #[test]
+    fn test_count_lines_c_style() {
+        let rust = language_for("rs").unwrap();
+        let text = "// header\n\nfn main() { /* inline */\n    /* start\n       end */ let x = 1;\n}\n/* a\n\n b */\n";
+        assert_eq!(count_lines(text, rust), counts(3, 4, 2));
+    }
+
+    #[test]
+    fn test_count_lines_line_comment_prefix_of_block() {
+        let lua = language_for("lua").unwrap();
+        let text = "-- note\n--[[ long\ncomment ]]\nprint(1) -- trailing\n";
+        assert_eq!(count_lines(text, lua), counts(1, 3, 0));
+
+        let python = language_for("py").unwrap();
+        let text = "def f():\n    \"\"\"Doc\n    more\n    \"\"\"\n    # hi\n    return 1\n";
+        assert_eq!(count_lines(text, python), counts(2, 4, 0));
+    }
+
+    #[test]
+    fn test_code_extensions_are_languages() {
+        for ext in ["rs", "py", "toml", "yaml", "html", "clj", "tsx", "vue"] {
+            assert!(language_for(ext).is_some(), "{}", ext);
+        }
+        assert!(language_for("md").is_none());
+    }
+}
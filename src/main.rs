@@ -4,17 +4,30 @@ static GLOBAL: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
 
 mod cli;
 mod commands;
+mod hash_cache;
 mod index;
 mod index_cache;
+mod journal;
+mod languages;
 mod mcp;
+mod media_date;
 mod output;
+mod rules;
 mod scanner;
+mod snapshot;
+mod sniff;
+mod template;
+mod transfer;
 
 use clap::Parser;
 
-use cli::{Cli, Commands};
+use cli::{ChecksumCommands, Cli, Commands};
 use mcp::server::run_mcp_server;
-use output::{print_duplicates, print_organize, print_search, print_stats};
+use output::{
+    print_checksum_create, print_checksum_verify, print_clean, print_cold, print_diff,
+    print_duplicates, print_find_copies, print_organize, print_rename, print_search, print_similar,
+    print_stats, print_undo, print_undo_runs,
+};
 
 fn main() {
     let cli = Cli::parse();
@@ -28,21 +41,180 @@ fn main() {
         Some(Commands::Stats {
             directory,
             top,
+            by_type,
+            code,
+            by,
+            audit,
+            tree,
+            depth,
+            disk_usage,
+            apparent_size: _,
+            histograms,
+            save_snapshot,
+            compare,
             recursive,
         }) => {
-            let result = commands::stats::run_stats(&directory, top, recursive);
+            let result = commands::stats::run_stats(&commands::stats::StatsParams {
+                directory: &directory,
+                top_n: top,
+                recursive,
+                by_type,
+                code,
+                by: by.as_deref(),
+                audit,
+                tree,
+                depth,
+                disk_usage,
+                histograms,
+                save_snapshot,
+                compare: compare.as_deref(),
+            });
             print_stats(&result);
+            if !result.errors.is_empty() {
+                std::process::exit(1);
+            }
         }
 
         Some(Commands::Duplicates {
             directory,
             min_size,
+            disk_usage,
+            apparent_size: _,
             recursive,
         }) => {
-            let result = commands::duplicates::run_duplicates(&directory, min_size, recursive);
+            let result =
+                commands::duplicates::run_duplicates(&directory, min_size, recursive, disk_usage);
             print_duplicates(&result);
         }
 
+        Some(Commands::Cold {
+            directory,
+            older,
+            min_size,
+            top,
+            by_access,
+            recursive,
+        }) => {
+            let result = commands::cold::run_cold(&commands::cold::ColdParams {
+                directory: &directory,
+                older: &older,
+                min_size: &min_size,
+                top_n: top,
+                by_access,
+                recursive,
+            });
+            print_cold(&result);
+            if !result.errors.is_empty() {
+                std::process::exit(1);
+            }
+        }
+
+        Some(Commands::Checksum { action }) => match action {
+            ChecksumCommands::Create {
+                directory,
+                output,
+                algo,
+                recursive,
+            } => {
+                let result = commands::checksum::run_checksum_create(
+                    &directory,
+                    output.as_deref(),
+                    &algo,
+                    recursive,
+                );
+                if result.manifest.is_none() && result.errors.is_empty() {
+                    print!("{}", result.manifest_text);
+                } else {
+                    print_checksum_create(&result);
+                }
+                if !result.errors.is_empty() {
+                    std::process::exit(1);
+                }
+            }
+            ChecksumCommands::Verify {
+                manifest,
+                root,
+                algo,
+                recursive,
+            } => {
+                let result = commands::checksum::run_checksum_verify(
+                    &manifest,
+                    root.as_deref(),
+                    algo.as_deref(),
+                    recursive,
+                );
+                print_checksum_verify(&result);
+                if !result.passed() {
+                    std::process::exit(1);
+                }
+            }
+        },
+
+        Some(Commands::Diff {
+            left,
+            right,
+            verify,
+            json,
+            recursive,
+        }) => {
+            for dir in [&left, &right] {
+                if !std::path::Path::new(dir).is_dir() {
+                    eprintln!("Not a directory: {}", dir);
+                    std::process::exit(1);
+                }
+            }
+            let result = commands::diff::run_diff(&left, &right, verify, recursive);
+            if json {
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&result).unwrap_or_default()
+                );
+            } else {
+                print_diff(&result);
+            }
+        }
+
+        Some(Commands::FindCopies {
+            target,
+            directories,
+            no_cache,
+            recursive,
+        }) => {
+            let result =
+                commands::find_copies::run_find_copies(&target, &directories, !no_cache, recursive);
+            print_find_copies(&result);
+            if !result.errors.is_empty() {
+                std::process::exit(1);
+            }
+        }
+
+        Some(Commands::Similar {
+            directory,
+            images,
+            algorithm,
+            threshold,
+            text,
+            min_similarity,
+            min_size,
+            recursive,
+        }) => {
+            if !images && !text {
+                eprintln!("No comparison mode specified. Use --images and/or --text.");
+                std::process::exit(1);
+            }
+            let result = commands::similar::run_similar(&commands::similar::SimilarParams {
+                directory: &directory,
+                images,
+                algorithm,
+                threshold,
+                text,
+                min_similarity,
+                min_size,
+                recursive,
+            });
+            print_similar(&result);
+        }
+
         Some(Commands::Search {
             directory,
             name,
@@ -51,6 +223,7 @@ fn main() {
             max_size,
             newer,
             older,
+            media_date,
             recursive,
         }) => {
             let result = commands::search::run_search(&commands::search::SearchParams {
@@ -61,6 +234,7 @@ fn main() {
                 max_size: max_size.as_deref(),
                 newer: newer.as_deref(),
                 older: older.as_deref(),
+                media_date,
                 recursive,
             });
             print_search(&result);
@@ -71,18 +245,121 @@ fn main() {
             by,
             dry_run,
             mode,
+            action,
             recursive,
             output,
+            hash,
+            rules,
+            template,
+            media_date,
+            prune_empty,
+            keep_going,
+            watch,
+            settle,
         }) => {
-            let result = commands::organize::run_organize(
-                &directory,
-                &by,
+            let params = commands::organize::OrganizeParams {
+                directory: &directory,
+                by: &by,
                 dry_run,
-                &mode,
+                mode: &mode,
+                action: &action,
                 recursive,
-                output.as_deref(),
+                output: output.as_deref(),
+                hash,
+                rules: rules.as_deref(),
+                template: template.as_deref(),
+                media_date,
+                prune_empty,
+                keep_going,
+            };
+            if !watch {
+                print_organize(&commands::organize::run_organize(&params));
+                return;
+            }
+            if dry_run {
+                eprintln!("--watch cannot be combined with --dry-run");
+                std::process::exit(1);
+            }
+
+            eprintln!("Watching {} (Ctrl-C to stop)", directory);
+            let result = commands::watch::watch_organize(
+                &params,
+                std::time::Duration::from_secs(settle),
+                |result| {
+                    // Quiet passes, e.g. after our own moves, print nothing
+                    if !result.moves.is_empty()
+                        || !result.identical.is_empty()
+                        || !result.errors.is_empty()
+                    {
+                        print_organize(&result);
+                    }
+                },
             );
-            print_organize(&result);
+            if let Err(e) = result {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+
+        Some(Commands::Rename {
+            directory,
+            pattern,
+            to,
+            apply,
+            media_date,
+            recursive,
+        }) => {
+            let result = commands::rename::run_rename(&commands::rename::RenameParams {
+                directory: &directory,
+                pattern: &pattern,
+                to: &to,
+                dry_run: !apply,
+                media_date,
+                recursive,
+            });
+            print_rename(&result);
+            if !result.conflicts.is_empty() || !result.errors.is_empty() {
+                std::process::exit(1);
+            }
+        }
+
+        Some(Commands::Clean {
+            directory,
+            kinds,
+            junk,
+            no_default_junk,
+            trash,
+            recursive,
+        }) => {
+            let result = commands::clean::run_clean(&commands::clean::CleanParams {
+                directory: &directory,
+                kinds: &kinds,
+                junk: &junk,
+                no_default_junk,
+                trash,
+                recursive,
+            });
+            print_clean(&result);
+            if !result.errors.is_empty() {
+                std::process::exit(1);
+            }
+        }
+
+        Some(Commands::Undo {
+            run_id,
+            dry_run,
+            force,
+            list,
+        }) => {
+            if list {
+                print_undo_runs(&journal::list_runs());
+                return;
+            }
+            let result = commands::undo::run_undo(run_id.as_deref(), dry_run, force);
+            print_undo(&result);
+            if !result.errors.is_empty() {
+                std::process::exit(1);
+            }
         }
 
         None => {
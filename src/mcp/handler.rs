@@ -2,7 +2,8 @@ use std::path::Path;
 
 use serde_json::Value;
 
-use crate::commands::{duplicates, organize, search, stats};
+use crate::commands::{diff, duplicates, find_copies, organize, rename, search, stats, undo};
+use crate::journal;
 use crate::mcp::protocol::ToolResult;
 
 /// Route a tools/call request to the appropriate command function.
@@ -10,10 +11,15 @@ use crate::mcp::protocol::ToolResult;
 pub fn handle_tool_call(name: &str, arguments: &Value) -> Result<ToolResult, String> {
     match name {
         "scan_stats" => Ok(handle_scan_stats(arguments)),
+        "stats_diff" => Ok(handle_stats_diff(arguments)),
         "find_duplicates" => Ok(handle_find_duplicates(arguments)),
         "search_files" => Ok(handle_search_files(arguments)),
         "organize_files" => Ok(handle_organize_files(arguments)),
+        "rename_files" => Ok(handle_rename_files(arguments)),
         "build_index" => Ok(handle_build_index(arguments)),
+        "compare_directories" => Ok(handle_compare_directories(arguments)),
+        "find_by_hash" => Ok(handle_find_by_hash(arguments)),
+        "undo_organize" => Ok(handle_undo_organize(arguments)),
         _ => Err(format!("Unknown tool: {}", name)),
     }
 }
@@ -24,18 +30,90 @@ fn handle_scan_stats(args: &Value) -> ToolResult {
         None => return ToolResult::error("Missing required parameter: directory".to_string()),
     };
     let top_n = args.get("top_n").and_then(|v| v.as_u64()).unwrap_or(10) as usize;
+    let by_type = args
+        .get("by_type")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    let code = args.get("code").and_then(|v| v.as_bool()).unwrap_or(false);
+    let by = args.get("by").and_then(|v| v.as_str());
+    let audit = args.get("audit").and_then(|v| v.as_bool()).unwrap_or(false);
     let recursive = args
         .get("recursive")
         .and_then(|v| v.as_bool())
         .unwrap_or(true);
 
-    let result = stats::run_stats(directory, top_n, recursive);
+    let tree = args.get("tree").and_then(|v| v.as_bool()).unwrap_or(false);
+    let depth = args.get("depth").and_then(|v| v.as_u64()).unwrap_or(2) as usize;
+    let disk_usage = args
+        .get("disk_usage")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    let histograms = args
+        .get("histograms")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    let save_snapshot = args
+        .get("save_snapshot")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+
+    let result = stats::run_stats(&stats::StatsParams {
+        directory,
+        top_n,
+        recursive,
+        by_type,
+        code,
+        by,
+        audit,
+        tree,
+        depth,
+        disk_usage,
+        histograms,
+        save_snapshot,
+        compare: None,
+    });
     match serde_json::to_string_pretty(&result) {
         Ok(json) => ToolResult::text(json),
         Err(e) => ToolResult::error(format!("Serialization error: {}", e)),
     }
 }
 
+fn handle_stats_diff(args: &Value) -> ToolResult {
+    let directory = match args.get("directory").and_then(|v| v.as_str()) {
+        Some(d) => d,
+        None => return ToolResult::error("Missing required parameter: directory".to_string()),
+    };
+    let snapshot = args
+        .get("snapshot")
+        .and_then(|v| v.as_str())
+        .unwrap_or("last");
+    let top_n = args.get("top_n").and_then(|v| v.as_u64()).unwrap_or(10) as usize;
+    let depth = args.get("depth").and_then(|v| v.as_u64()).unwrap_or(2) as usize;
+
+    let result = stats::run_stats(&stats::StatsParams {
+        directory,
+        top_n,
+        recursive: true,
+        by_type: false,
+        code: false,
+        by: None,
+        audit: false,
+        tree: false,
+        depth,
+        disk_usage: false,
+        histograms: false,
+        save_snapshot: false,
+        compare: Some(snapshot),
+    });
+    let Some(changes) = result.changes else {
+        return ToolResult::error(result.errors.join("; "));
+    };
+    match serde_json::to_string_pretty(&changes) {
+        Ok(json) => ToolResult::text(json),
+        Err(e) => ToolResult::error(format!("Serialization error: {}", e)),
+    }
+}
+
 fn handle_find_duplicates(args: &Value) -> ToolResult {
     let directory = match args.get("directory").and_then(|v| v.as_str()) {
         Some(d) => d,
@@ -47,7 +125,12 @@ fn handle_find_duplicates(args: &Value) -> ToolResult {
         .and_then(|v| v.as_bool())
         .unwrap_or(true);
 
-    let result = duplicates::run_duplicates(directory, min_size, recursive);
+    let disk_usage = args
+        .get("disk_usage")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+
+    let result = duplicates::run_duplicates(directory, min_size, recursive, disk_usage);
     match serde_json::to_string_pretty(&result) {
         Ok(json) => ToolResult::text(json),
         Err(e) => ToolResult::error(format!("Serialization error: {}", e)),
@@ -65,6 +148,10 @@ fn handle_search_files(args: &Value) -> ToolResult {
     let max_size = args.get("max_size").and_then(|v| v.as_str());
     let newer = args.get("newer").and_then(|v| v.as_str());
     let older = args.get("older").and_then(|v| v.as_str());
+    let media_date = args
+        .get("media_date")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
     let recursive = args
         .get("recursive")
         .and_then(|v| v.as_bool())
@@ -80,6 +167,7 @@ fn handle_search_files(args: &Value) -> ToolResult {
             max_size,
             newer,
             older,
+            media_date,
             recursive,
         },
         true,
@@ -90,6 +178,69 @@ fn handle_search_files(args: &Value) -> ToolResult {
     }
 }
 
+fn handle_compare_directories(args: &Value) -> ToolResult {
+    let left = match args.get("left").and_then(|v| v.as_str()) {
+        Some(d) => d,
+        None => return ToolResult::error("Missing required parameter: left".to_string()),
+    };
+    let right = match args.get("right").and_then(|v| v.as_str()) {
+        Some(d) => d,
+        None => return ToolResult::error("Missing required parameter: right".to_string()),
+    };
+    let verify = args
+        .get("verify")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    let recursive = args
+        .get("recursive")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(true);
+
+    for dir in [left, right] {
+        if !Path::new(dir).is_dir() {
+            return ToolResult::error(format!("Not a directory: {}", dir));
+        }
+    }
+
+    let result = diff::run_diff(left, right, verify, recursive);
+    match serde_json::to_string_pretty(&result) {
+        Ok(json) => ToolResult::text(json),
+        Err(e) => ToolResult::error(format!("Serialization error: {}", e)),
+    }
+}
+
+fn handle_find_by_hash(args: &Value) -> ToolResult {
+    let target = match args
+        .get("hash")
+        .or_else(|| args.get("file"))
+        .and_then(|v| v.as_str())
+    {
+        Some(t) => t,
+        None => return ToolResult::error("Missing required parameter: hash or file".to_string()),
+    };
+    let directories: Vec<String> = match args.get("directories").and_then(|v| v.as_array()) {
+        Some(dirs) => dirs
+            .iter()
+            .filter_map(|d| d.as_str().map(String::from))
+            .collect(),
+        None => return ToolResult::error("Missing required parameter: directories".to_string()),
+    };
+    let use_cache = args
+        .get("use_cache")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(true);
+    let recursive = args
+        .get("recursive")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(true);
+
+    let result = find_copies::run_find_copies(target, &directories, use_cache, recursive);
+    match serde_json::to_string_pretty(&result) {
+        Ok(json) => ToolResult::text(json),
+        Err(e) => ToolResult::error(format!("Serialization error: {}", e)),
+    }
+}
+
 fn handle_build_index(args: &Value) -> ToolResult {
     let directory = match args.get("directory").and_then(|v| v.as_str()) {
         Some(d) => d,
@@ -124,13 +275,106 @@ fn handle_organize_files(args: &Value) -> ToolResult {
         .get("mode")
         .and_then(|v| v.as_str())
         .unwrap_or("rename");
+    let action = args
+        .get("action")
+        .and_then(|v| v.as_str())
+        .unwrap_or("move");
     let recursive = args
         .get("recursive")
         .and_then(|v| v.as_bool())
         .unwrap_or(true);
     let output = args.get("output").and_then(|v| v.as_str());
+    let hash = args.get("hash").and_then(|v| v.as_bool()).unwrap_or(false);
+    let rules = args.get("rules").and_then(|v| v.as_str());
+    let template = args.get("template").and_then(|v| v.as_str());
+    let media_date = args
+        .get("media_date")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    let prune_empty = args
+        .get("prune_empty")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    let keep_going = args
+        .get("keep_going")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+
+    let result = organize::run_organize(&organize::OrganizeParams {
+        directory,
+        by,
+        dry_run,
+        mode,
+        action,
+        recursive,
+        output,
+        hash,
+        rules,
+        template,
+        media_date,
+        prune_empty,
+        keep_going,
+    });
+    match serde_json::to_string_pretty(&result) {
+        Ok(json) => ToolResult::text(json),
+        Err(e) => ToolResult::error(format!("Serialization error: {}", e)),
+    }
+}
+
+fn handle_rename_files(args: &Value) -> ToolResult {
+    let directory = match args.get("directory").and_then(|v| v.as_str()) {
+        Some(d) => d,
+        None => return ToolResult::error("Missing required parameter: directory".to_string()),
+    };
+    let pattern = match args.get("match").and_then(|v| v.as_str()) {
+        Some(p) => p,
+        None => return ToolResult::error("Missing required parameter: match".to_string()),
+    };
+    let to = match args.get("to").and_then(|v| v.as_str()) {
+        Some(t) => t,
+        None => return ToolResult::error("Missing required parameter: to".to_string()),
+    };
+    let dry_run = args
+        .get("dry_run")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(true);
+    let media_date = args
+        .get("media_date")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    let recursive = args
+        .get("recursive")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(true);
+
+    let result = rename::run_rename(&rename::RenameParams {
+        directory,
+        pattern,
+        to,
+        dry_run,
+        media_date,
+        recursive,
+    });
+    match serde_json::to_string_pretty(&result) {
+        Ok(json) => ToolResult::text(json),
+        Err(e) => ToolResult::error(format!("Serialization error: {}", e)),
+    }
+}
+
+fn handle_undo_organize(args: &Value) -> ToolResult {
+    let run_id = args.get("run_id").and_then(|v| v.as_str());
+    if let Some(id) = run_id
+        && !journal::is_valid_run_id(id)
+    {
+        return ToolResult::error(format!("Invalid run id: {}", id));
+    }
+    let dry_run = args
+        .get("dry_run")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(true);
+    let force = args.get("force").and_then(|v| v.as_bool()).unwrap_or(false);
 
-    let result = organize::run_organize(directory, by, dry_run, mode, recursive, output);
+    let result = undo::run_undo(run_id, dry_run, force);
     match serde_json::to_string_pretty(&result) {
         Ok(json) => ToolResult::text(json),
         Err(e) => ToolResult::error(format!("Serialization error: {}", e)),
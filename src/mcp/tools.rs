@@ -6,7 +6,7 @@ pub fn tool_definitions() -> Value {
         "tools": [
             {
                 "name": "scan_stats",
-                "description": "Get file statistics for a directory: total files, total size, breakdown by extension, and largest files.",
+                "description": "Get file statistics for a directory: total files, total size, breakdown by extension (and optionally by content-detected MIME type), largest files, and optionally per-directory disk usage, size/age histograms, lines of code per language, owner/group breakdowns and a permission audit.",
                 "inputSchema": {
                     "type": "object",
                     "properties": {
@@ -19,6 +19,51 @@ pub fn tool_definitions() -> Value {
                             "description": "Number of largest files to return",
                             "default": 10
                         },
+                        "by_type": {
+                            "type": "boolean",
+                            "description": "Also break down by MIME type detected from file contents (magic bytes), which catches extensionless and mislabeled files",
+                            "default": false
+                        },
+                        "code": {
+                            "type": "boolean",
+                            "description": "Also classify source files into languages by extension and count code, comment and blank lines per language, like tokei",
+                            "default": false
+                        },
+                        "by": {
+                            "type": "string",
+                            "description": "Also break down by owning user ('owner') or group ('group')",
+                            "enum": ["owner", "group"]
+                        },
+                        "audit": {
+                            "type": "boolean",
+                            "description": "Also audit permissions: world-writable files, setuid/setgid executables, and files not owned by the current user (each counted, largest top_n listed)",
+                            "default": false
+                        },
+                        "tree": {
+                            "type": "boolean",
+                            "description": "Also return a du-style tree of cumulative size and file count per directory (children sorted largest first) and a largest_dirs list of directories holding the most data directly",
+                            "default": false
+                        },
+                        "depth": {
+                            "type": "integer",
+                            "description": "Directory levels below the scanned directory to include in the tree",
+                            "default": 2
+                        },
+                        "disk_usage": {
+                            "type": "boolean",
+                            "description": "Report allocated disk space (st_blocks) instead of apparent size, so sparse and compressed files count for what they occupy",
+                            "default": false
+                        },
+                        "save_snapshot": {
+                            "type": "boolean",
+                            "description": "Save a compact snapshot of per-directory and per-extension totals (and files of 1 MB or more) to the cache directory, for later comparison with stats_diff",
+                            "default": false
+                        },
+                        "histograms": {
+                            "type": "boolean",
+                            "description": "Also return log-scale size and modification-age histograms (count and bytes per bucket: <1 day, <1 week, <1 month, <1 year, older), overall and per extension, to spot cold data",
+                            "default": false
+                        },
                         "recursive": {
                             "type": "boolean",
                             "description": "Scan subdirectories",
@@ -28,6 +73,35 @@ pub fn tool_definitions() -> Value {
                     "required": ["directory"]
                 }
             },
+            {
+                "name": "stats_diff",
+                "description": "Report what changed in a directory since a snapshot saved by scan_stats (save_snapshot) or `fiq stats --save-snapshot`: total, per-directory and per-extension growth, new large files and deleted large files. Sizes are measured the same way as the snapshot.",
+                "inputSchema": {
+                    "type": "object",
+                    "properties": {
+                        "directory": {
+                            "type": "string",
+                            "description": "Directory the snapshot was taken of"
+                        },
+                        "snapshot": {
+                            "type": "string",
+                            "description": "Snapshot id or file to compare against, or \"last\" for the most recent one",
+                            "default": "last"
+                        },
+                        "depth": {
+                            "type": "integer",
+                            "description": "Directory levels below the scanned directory to report growth for",
+                            "default": 2
+                        },
+                        "top_n": {
+                            "type": "integer",
+                            "description": "Maximum number of directories and of new or deleted files to return",
+                            "default": 10
+                        }
+                    },
+                    "required": ["directory"]
+                }
+            },
             {
                 "name": "find_duplicates",
                 "description": "Find duplicate files by content hash (blake3). Groups files by size first, then hashes only potential duplicates for speed.",
@@ -43,6 +117,11 @@ pub fn tool_definitions() -> Value {
                             "description": "Minimum file size in bytes to consider",
                             "default": 1
                         },
+                        "disk_usage": {
+                            "type": "boolean",
+                            "description": "Count wasted space as allocated disk space instead of apparent size",
+                            "default": false
+                        },
                         "recursive": {
                             "type": "boolean",
                             "description": "Scan subdirectories",
@@ -86,6 +165,11 @@ pub fn tool_definitions() -> Value {
                             "type": "string",
                             "description": "Files modified before this time (e.g. '2024-01-01', '7d', '24h')"
                         },
+                        "media_date": {
+                            "type": "boolean",
+                            "description": "For photos and videos, compare the embedded capture time (EXIF DateTimeOriginal, MP4/MOV creation time) instead of mtime",
+                            "default": false
+                        },
                         "recursive": {
                             "type": "boolean",
                             "description": "Search subdirectories",
@@ -97,7 +181,7 @@ pub fn tool_definitions() -> Value {
             },
             {
                 "name": "organize_files",
-                "description": "Organize files into folders by type, date, or size. Supports dry-run mode to preview changes without moving files.",
+                "description": "Organize files into folders by type, date, size, user-defined rules, or a path template. Supports dry-run mode to preview changes without moving files. Real runs are checked up front and roll back on failure; completed runs are journaled and return a run_id for undo_organize.",
                 "inputSchema": {
                     "type": "object",
                     "properties": {
@@ -107,10 +191,18 @@ pub fn tool_definitions() -> Value {
                         },
                         "by": {
                             "type": "string",
-                            "description": "Organization strategy: 'type', 'date', or 'size'",
-                            "enum": ["type", "date", "size"],
+                            "description": "Organization strategy: 'type' (by extension), 'mime' (by detected content type), 'date', 'size', 'rules', or 'template'",
+                            "enum": ["type", "mime", "date", "size", "rules", "template"],
                             "default": "type"
                         },
+                        "rules": {
+                            "type": "string",
+                            "description": "Path to a TOML rules file; implies by='rules' (default: ~/.config/fiq/rules.toml)"
+                        },
+                        "template": {
+                            "type": "string",
+                            "description": "Destination path template; implies by='template'. Placeholders: {name}, {stem}, {ext}, {category}, {date}, {year}, {month}, {day}, {size}, {parent}, {hash} or {hash:N}, {n} or {n:3} counter (files numbered in path order). Add :upper, :lower or :title to any placeholder"
+                        },
                         "dry_run": {
                             "type": "boolean",
                             "description": "Preview changes without moving files",
@@ -118,10 +210,16 @@ pub fn tool_definitions() -> Value {
                         },
                         "mode": {
                             "type": "string",
-                            "description": "Collision handling: 'skip', 'rename', or 'overwrite'",
-                            "enum": ["skip", "rename", "overwrite"],
+                            "description": "Collision handling: 'skip', 'rename', 'overwrite', 'dedupe' (blake3-compare; drop identical sources, rename different ones), or 'newer-wins' (replace only when the source has a newer mtime)",
+                            "enum": ["skip", "rename", "overwrite", "dedupe", "newer-wins"],
                             "default": "rename"
                         },
+                        "action": {
+                            "type": "string",
+                            "description": "How to place files: 'move', or 'copy', 'hardlink', 'symlink' to build an organized view while leaving the source untouched",
+                            "enum": ["move", "copy", "hardlink", "symlink"],
+                            "default": "move"
+                        },
                         "recursive": {
                             "type": "boolean",
                             "description": "Process subdirectories",
@@ -130,6 +228,26 @@ pub fn tool_definitions() -> Value {
                         "output": {
                             "type": "string",
                             "description": "Output directory (default: organize in-place)"
+                        },
+                        "hash": {
+                            "type": "boolean",
+                            "description": "Record blake3 hashes in the undo journal so undo can detect edits",
+                            "default": false
+                        },
+                        "media_date": {
+                            "type": "boolean",
+                            "description": "Date photos and videos by embedded capture time (EXIF DateTimeOriginal, MP4/MOV creation time), falling back to mtime",
+                            "default": false
+                        },
+                        "prune_empty": {
+                            "type": "boolean",
+                            "description": "Remove directories left empty after moving files out of them (not in dry-run)",
+                            "default": false
+                        },
+                        "keep_going": {
+                            "type": "boolean",
+                            "description": "Skip files that fail preflight checks or execution instead of aborting. By default any preflight problem (unwritable folder, file in the way, not enough space) aborts before anything is touched, and a failure mid-run rolls back what was done",
+                            "default": false
                         }
                     },
                     "required": ["directory"]
@@ -148,6 +266,127 @@ pub fn tool_definitions() -> Value {
                     },
                     "required": ["directory"]
                 }
+            },
+            {
+                "name": "compare_directories",
+                "description": "Compare two directory trees by relative path: files only in left, only in right, modified (by size/mtime, or blake3 with verify), unchanged count, and files moved or renamed between the trees (matched by hash).",
+                "inputSchema": {
+                    "type": "object",
+                    "properties": {
+                        "left": {
+                            "type": "string",
+                            "description": "Left directory (e.g. the backup)"
+                        },
+                        "right": {
+                            "type": "string",
+                            "description": "Right directory (e.g. the live copy)"
+                        },
+                        "verify": {
+                            "type": "boolean",
+                            "description": "Confirm same-size files with blake3 instead of trusting mtime",
+                            "default": false
+                        },
+                        "recursive": {
+                            "type": "boolean",
+                            "description": "Compare subdirectories",
+                            "default": true
+                        }
+                    },
+                    "required": ["left", "right"]
+                }
+            },
+            {
+                "name": "find_by_hash",
+                "description": "Find every copy of a file across directories by blake3 content hash. Pass either a file path (only same-size files are hashed) or a hex digest. Hashes are cached on disk (~/.cache/fiq/), so repeated lookups skip unchanged files and digest queries on a fully cached directory are answered without a walk.",
+                "inputSchema": {
+                    "type": "object",
+                    "properties": {
+                        "file": {
+                            "type": "string",
+                            "description": "File whose copies to find"
+                        },
+                        "hash": {
+                            "type": "string",
+                            "description": "blake3 hex digest to look for (alternative to file)"
+                        },
+                        "directories": {
+                            "type": "array",
+                            "items": { "type": "string" },
+                            "description": "Directories to search"
+                        },
+                        "use_cache": {
+                            "type": "boolean",
+                            "description": "Use and update the persisted hash cache",
+                            "default": true
+                        },
+                        "recursive": {
+                            "type": "boolean",
+                            "description": "Search subdirectories",
+                            "default": true
+                        }
+                    },
+                    "required": ["directories"]
+                }
+            },
+            {
+                "name": "rename_files",
+                "description": "Rename files whose name matches a regex, building new names from a template. The whole batch is checked for collisions first (chains and swaps are handled); any conflict stops the batch. Previews by default; real runs are journaled and return a run_id for undo_organize.",
+                "inputSchema": {
+                    "type": "object",
+                    "properties": {
+                        "directory": {
+                            "type": "string",
+                            "description": "Directory with the files to rename"
+                        },
+                        "match": {
+                            "type": "string",
+                            "description": "Regex matched against file names; only matching files are renamed"
+                        },
+                        "to": {
+                            "type": "string",
+                            "description": "New name template: {1}, {2}... capture groups, {n} or {n:3} counter, {name}, {stem}, {ext}, {date}, {year}, {month}, {day}, {parent}, {hash:N}. Add :upper, :lower or :title to any placeholder"
+                        },
+                        "dry_run": {
+                            "type": "boolean",
+                            "description": "Preview the new names without renaming",
+                            "default": true
+                        },
+                        "media_date": {
+                            "type": "boolean",
+                            "description": "Use photo/video capture time (EXIF, MP4/MOV) for date placeholders",
+                            "default": false
+                        },
+                        "recursive": {
+                            "type": "boolean",
+                            "description": "Rename in subdirectories too",
+                            "default": true
+                        }
+                    },
+                    "required": ["directory", "match", "to"]
+                }
+            },
+            {
+                "name": "undo_organize",
+                "description": "Reverse an organize, rename or clean run from its journal, newest move first. Moved and renamed files, and entries clean moved to the trash, go back to where they were. Files changed since the move, or whose original location is occupied again, are skipped and kept in the journal for a retry.",
+                "inputSchema": {
+                    "type": "object",
+                    "properties": {
+                        "run_id": {
+                            "type": "string",
+                            "description": "Run id returned by organize_files or rename_files, or printed by fiq clean --trash (default: the most recent run)"
+                        },
+                        "dry_run": {
+                            "type": "boolean",
+                            "description": "Preview what would be restored without moving files",
+                            "default": true
+                        },
+                        "force": {
+                            "type": "boolean",
+                            "description": "Restore files even if they changed since the move",
+                            "default": false
+                        }
+                    }
+                }
             }
         ]
     })
@@ -0,0 +1,175 @@
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use chrono::{Local, NaiveDate, TimeZone};
+use exif::{In, Reader, Tag, Value};
+
+/// Seconds between the QuickTime epoch (1904-01-01) and the Unix epoch.
+const QUICKTIME_EPOCH_OFFSET: u64 = 2_082_844_800;
+
+/// Capture time embedded in a photo or video: EXIF DateTimeOriginal for
+/// images, the movie header creation time for MP4/MOV. Returns None for
+/// other files and for media without a usable timestamp.
+pub fn capture_time(path: &Path) -> Option<SystemTime> {
+    let ext = path.extension()?.to_str()?.to_lowercase();
+    match ext.as_str() {
+        "jpg" | "jpeg" | "tif" | "tiff" | "heic" | "heif" | "avif" | "png" | "webp" | "dng" => {
+            let mut reader = BufReader::new(File::open(path).ok()?);
+            exif_time(&mut reader)
+        }
+        "mp4" | "mov" | "m4v" | "3gp" => {
+            let mut reader = BufReader::new(File::open(path).ok()?);
+            mvhd_time(&mut reader)
+        }
+        _ => None,
+    }
+}
+
+/// DateTimeOriginal (or DateTimeDigitized), honoring OffsetTimeOriginal
+/// when present. Without an offset the time is taken as local time, which
+/// is what cameras record.
+fn exif_time<R: std::io::BufRead + Seek>(reader: &mut R) -> Option<SystemTime> {
+    let exif = Reader::new().read_from_container(reader).ok()?;
+    let ascii = |tag: Tag| match exif.get_field(tag, In::PRIMARY).map(|f| &f.value) {
+        Some(Value::Ascii(v)) => v.first().cloned(),
+        _ => None,
+    };
+
+    let (raw, offset_tag) = match ascii(Tag::DateTimeOriginal) {
+        Some(raw) => (raw, Tag::OffsetTimeOriginal),
+        None => (ascii(Tag::DateTimeDigitized)?, Tag::OffsetTimeDigitized),
+    };
+    let mut dt = exif::DateTime::from_ascii(&raw).ok()?;
+    if let Some(offset) = ascii(offset_tag) {
+        let _ = dt.parse_offset(&offset);
+    }
+
+    let naive = NaiveDate::from_ymd_opt(dt.year.into(), dt.month.into(), dt.day.into())?
+        .and_hms_opt(dt.hour.into(), dt.minute.into(), dt.second.into())?;
+    match dt.offset {
+        Some(minutes) => {
+            let utc = naive - chrono::Duration::minutes(minutes.into());
+            Some(utc.and_utc().into())
+        }
+        None => Some(Local.from_local_datetime(&naive).earliest()?.into()),
+    }
+}
+
+/// Read one ISO-BMFF box header: (type, payload size). A size of None
+/// means the box runs to the end of the file.
+fn box_header<R: Read>(reader: &mut R) -> Option<([u8; 4], Option<u64>)> {
+    let mut header = [0u8; 8];
+    reader.read_exact(&mut header).ok()?;
+    let size = u32::from_be_bytes(header[0..4].try_into().ok()?) as u64;
+    let kind: [u8; 4] = header[4..8].try_into().ok()?;
+    let payload = match size {
+        0 => None,
+        1 => {
+            let mut large = [0u8; 8];
+            reader.read_exact(&mut large).ok()?;
+            Some(u64::from_be_bytes(large).checked_sub(16)?)
+        }
+        n => Some(n.checked_sub(8)?),
+    };
+    Some((kind, payload))
+}
+
+/// Walk boxes until one of type `kind`, returning its payload size.
+/// Stops after `limit` bytes when given.
+fn find_box<R: Read + Seek>(reader: &mut R, kind: &[u8; 4], limit: Option<u64>) -> Option<u64> {
+    let mut consumed = 0u64;
+    while limit.is_none_or(|l| consumed < l) {
+        let start = reader.stream_position().ok()?;
+        let (found, payload) = box_header(reader)?;
+        if &found == kind {
+            return Some(payload.unwrap_or(u64::MAX));
+        }
+        let payload = payload?;
+        reader.seek(SeekFrom::Current(payload as i64)).ok()?;
+        consumed += reader.stream_position().ok()? - start;
+    }
+    None
+}
+
+/// Creation time from the `moov/mvhd` movie header of an MP4/MOV file.
+fn mvhd_time<R: Read + Seek>(reader: &mut R) -> Option<SystemTime> {
+    let moov = find_box(reader, b"moov", None)?;
+    find_box(reader, b"mvhd", Some(moov))?;
+
+    let mut version = [0u8; 4];
+    reader.read_exact(&mut version).ok()?;
+    let seconds = if version[0] == 1 {
+        let mut buf = [0u8; 8];
+        reader.read_exact(&mut buf).ok()?;
+        u64::from_be_bytes(buf)
+    } else {
+        let mut buf = [0u8; 4];
+        reader.read_exact(&mut buf).ok()?;
+        u32::from_be_bytes(buf) as u64
+    };
+
+    // Zero means "not set"; anything before 1970 is equally bogus
+    let unix = seconds.checked_sub(QUICKTIME_EPOCH_OFFSET)?;
+    (unix > 0).then(|| SystemTime::UNIX_EPOCH + Duration::from_secs(unix))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn mp4_box(kind: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+        let mut out = ((payload.len() + 8) as u32).to_be_bytes().to_vec();
+        out.extend_from_slice(kind);
+        out.extend_from_slice(payload);
+        out
+    }
+
+    #[test]
+    fn test_mvhd_creation_time() {
+        // 2021-03-04 05:06:07 UTC
+        let unix = 1_614_834_367u64;
+        let mut mvhd = vec![0, 0, 0, 0];
+        mvhd.extend_from_slice(&((unix + QUICKTIME_EPOCH_OFFSET) as u32).to_be_bytes());
+        mvhd.extend_from_slice(&[0; 88]);
+
+        let mut file = mp4_box(b"ftyp", b"isom\0\0\0\0");
+        file.extend(mp4_box(b"free", &[0; 16]));
+        let mut moov = mp4_box(b"trak", &[0; 8]);
+        moov.extend(mp4_box(b"mvhd", &mvhd));
+        file.extend(mp4_box(b"moov", &moov));
+
+        assert_eq!(
+            mvhd_time(&mut Cursor::new(file)),
+            Some(SystemTime::UNIX_EPOCH + Duration::from_secs(unix))
+        );
+        assert_eq!(mvhd_time(&mut Cursor::new(mp4_box(b"ftyp", b"isom"))), None);
+    }
+
+    #[test]
+    fn test_exif_date_time_original() {
+        // Big-endian TIFF: IFD0 points to an Exif IFD holding
+        // DateTimeOriginal and OffsetTimeOriginal
+        let mut tiff = b"MM\0\x2a\0\0\0\x08".to_vec();
+        tiff.extend_from_slice(&[0, 1, 0x87, 0x69, 0, 4, 0, 0, 0, 1, 0, 0, 0, 26, 0, 0, 0, 0]);
+        tiff.extend_from_slice(&[0, 2]);
+        tiff.extend_from_slice(&[0x90, 0x03, 0, 2, 0, 0, 0, 20, 0, 0, 0, 56]);
+        tiff.extend_from_slice(&[0x90, 0x11, 0, 2, 0, 0, 0, 7, 0, 0, 0, 76]);
+        tiff.extend_from_slice(&[0, 0, 0, 0]);
+        tiff.extend_from_slice(b"2019:07:04 10:30:00\0");
+        tiff.extend_from_slice(b"+02:00\0");
+
+        // 10:30 at +02:00 is 08:30 UTC
+        let expected = NaiveDate::from_ymd_opt(2019, 7, 4)
+            .unwrap()
+            .and_hms_opt(8, 30, 0)
+            .unwrap()
+            .and_utc();
+        assert_eq!(
+            exif_time(&mut Cursor::new(tiff)),
+            Some(SystemTime::from(expected))
+        );
+    }
+}
@@ -1,10 +1,19 @@
 use std::io::Write;
 use termcolor::{Color, ColorChoice, ColorSpec, StandardStream, WriteColor};
 
+use crate::commands::checksum::{ChecksumCreateResult, ChecksumVerifyResult};
+use crate::commands::clean::CleanResult;
+use crate::commands::cold::ColdResult;
+use crate::commands::diff::DiffResult;
 use crate::commands::duplicates::DuplicatesResult;
+use crate::commands::find_copies::FindCopiesResult;
 use crate::commands::organize::OrganizeResult;
+use crate::commands::rename::RenameResult;
 use crate::commands::search::SearchResult;
-use crate::commands::stats::StatsResult;
+use crate::commands::similar::SimilarResult;
+use crate::commands::stats::{AuditFindings, Bucket, DirNode, OwnerStats, StatsResult};
+use crate::commands::undo::UndoResult;
+use crate::snapshot::{ChangedFile, Growth};
 
 /// Format a byte count into a human-readable string.
 pub fn format_size(bytes: u64) -> String {
@@ -47,8 +56,16 @@ pub fn print_stats(result: &StatsResult) {
     write_bold(&mut out, "  Total files: ");
     let _ = writeln!(out, "{}", result.total_files);
 
-    write_bold(&mut out, "  Total size:  ");
+    if result.disk_usage {
+        write_bold(&mut out, "  Disk usage:  ");
+    } else {
+        write_bold(&mut out, "  Total size:  ");
+    }
     let _ = writeln!(out, "{}", format_size(result.total_size));
+    if result.sparse_files > 0 {
+        write_bold(&mut out, "  Sparse or compressed files: ");
+        let _ = writeln!(out, "{}", result.sparse_files);
+    }
     let _ = writeln!(out);
 
     if !result.by_extension.is_empty() {
@@ -68,19 +85,518 @@ pub fn print_stats(result: &StatsResult) {
         let _ = writeln!(out);
     }
 
+    if let Some(ref histograms) = result.histograms
+        && result.total_files > 0
+    {
+        write_colored(&mut out, "  Files by Size\n", Color::Yellow);
+        print_histogram(&mut out, &histograms.size, false);
+        let _ = writeln!(out);
+
+        write_colored(&mut out, "  Data by Age (last modified)\n", Color::Yellow);
+        print_histogram(&mut out, &histograms.age, true);
+        let _ = writeln!(out);
+
+        write_colored(
+            &mut out,
+            "  Age by Extension (share of size)\n",
+            Color::Yellow,
+        );
+        let _ = write!(out, "  {:<15}", "Extension");
+        for bucket in &histograms.age {
+            let _ = write!(out, " {:>10}", bucket.label);
+        }
+        let _ = writeln!(out);
+        let _ = writeln!(out, "  {}", "-".repeat(15 + 11 * histograms.age.len()));
+        for ext in &result.by_extension {
+            let Some(ref h) = ext.histograms else {
+                continue;
+            };
+            let _ = write!(out, "  {:<15}", format!(".{}", ext.extension));
+            for bucket in &h.age {
+                let share = if ext.total_size > 0 {
+                    format!("{:.0}%", bucket.size as f64 * 100.0 / ext.total_size as f64)
+                } else {
+                    "-".to_string()
+                };
+                let _ = write!(out, " {:>10}", share);
+            }
+            let _ = writeln!(out);
+        }
+        let _ = writeln!(out);
+    }
+
+    if let Some(ref by_type) = result.by_type
+        && !by_type.is_empty()
+    {
+        write_colored(&mut out, "  By Detected Type\n", Color::Yellow);
+        let _ = writeln!(out, "  {:<32} {:>8} {:>12}", "Type", "Count", "Size");
+        let _ = writeln!(out, "  {}", "-".repeat(54));
+
+        for t in by_type {
+            let _ = writeln!(
+                out,
+                "  {:<32} {:>8} {:>12}",
+                t.mime,
+                t.count,
+                format_size(t.total_size)
+            );
+        }
+        let _ = writeln!(out);
+    }
+
+    if let Some(ref languages) = result.languages
+        && !languages.is_empty()
+    {
+        write_colored(&mut out, "  Languages\n", Color::Yellow);
+        let _ = writeln!(
+            out,
+            "  {:<16} {:>7} {:>10} {:>10} {:>10}",
+            "Language", "Files", "Code", "Comments", "Blanks"
+        );
+        let _ = writeln!(out, "  {}", "-".repeat(57));
+        for lang in languages {
+            let _ = writeln!(
+                out,
+                "  {:<16} {:>7} {:>10} {:>10} {:>10}",
+                lang.language, lang.files, lang.code, lang.comments, lang.blanks
+            );
+        }
+        let _ = writeln!(out, "  {}", "-".repeat(57));
+        let _ = writeln!(
+            out,
+            "  {:<16} {:>7} {:>10} {:>10} {:>10}",
+            "Total",
+            languages.iter().map(|l| l.files).sum::<usize>(),
+            languages.iter().map(|l| l.code).sum::<usize>(),
+            languages.iter().map(|l| l.comments).sum::<usize>(),
+            languages.iter().map(|l| l.blanks).sum::<usize>()
+        );
+        let _ = writeln!(out);
+    }
+
+    if let Some(ref owners) = result.by_owner {
+        print_owners(&mut out, "  By Owner\n", "Owner", owners);
+    }
+    if let Some(ref groups) = result.by_group {
+        print_owners(&mut out, "  By Group\n", "Group", groups);
+    }
+
     if !result.largest_files.is_empty() {
         write_colored(&mut out, "  Largest Files\n", Color::Yellow);
         for (i, file) in result.largest_files.iter().enumerate() {
             let _ = writeln!(
                 out,
-                "  {}. {} ({})",
+                "  {}. {} ({}){}",
                 i + 1,
                 file.path,
-                format_size(file.size)
+                format_size(file.size),
+                if file.sparse { " [sparse]" } else { "" }
+            );
+        }
+        let _ = writeln!(out);
+    }
+
+    if let Some(ref tree) = result.tree {
+        write_colored(&mut out, "  Directory Tree\n", Color::Yellow);
+        let _ = writeln!(out, "  {:>12} {:>8}  Directory", "Size", "Files");
+        let _ = writeln!(out, "  {}", "-".repeat(40));
+        print_dir_node(&mut out, tree, 0);
+        let _ = writeln!(out);
+    }
+
+    if let Some(ref dirs) = result.largest_dirs
+        && !dirs.is_empty()
+    {
+        write_colored(
+            &mut out,
+            "  Largest Directories (own files)\n",
+            Color::Yellow,
+        );
+        for (i, dir) in dirs.iter().enumerate() {
+            let _ = writeln!(
+                out,
+                "  {}. {} ({}, {} files)",
+                i + 1,
+                dir.path,
+                format_size(dir.size),
+                dir.files
+            );
+        }
+        let _ = writeln!(out);
+    }
+
+    if let Some(ref audit) = result.audit {
+        write_colored(&mut out, "  Permission Audit\n", Color::Yellow);
+        write_bold(&mut out, "  World-writable files: ");
+        print_audit_findings(&mut out, &audit.world_writable);
+        write_bold(&mut out, "  Setuid/setgid executables: ");
+        print_audit_findings(&mut out, &audit.setuid);
+        write_bold(&mut out, "  Not owned by ");
+        let _ = write!(out, "{}: ", audit.current_user);
+        print_audit_findings(&mut out, &audit.not_owned);
+        let _ = writeln!(out);
+    }
+
+    if let Some(ref changes) = result.changes {
+        write_colored(
+            &mut out,
+            &format!(
+                "  Changes since snapshot {} ({})\n",
+                changes.snapshot, changes.taken_at
+            ),
+            Color::Cyan,
+        );
+        write_bold(&mut out, "  Files: ");
+        let _ = writeln!(
+            out,
+            "{} → {} ({:+})",
+            changes.before.files,
+            changes.after.files,
+            changes.after.files as i64 - changes.before.files as i64
+        );
+        if changes.disk_usage {
+            write_bold(&mut out, "  Disk usage: ");
+        } else {
+            write_bold(&mut out, "  Size: ");
+        }
+        let _ = writeln!(
+            out,
+            "{} → {} ({})",
+            format_size(changes.before.size),
+            format_size(changes.after.size),
+            format_change(changes.after.size as i64 - changes.before.size as i64)
+        );
+        let _ = writeln!(out);
+
+        print_growth(
+            &mut out,
+            "  Growth by Directory\n",
+            "Directory",
+            &changes.directories,
+        );
+        print_growth(
+            &mut out,
+            "  Growth by Extension\n",
+            "Extension",
+            &changes.extensions,
+        );
+        print_changed_files(&mut out, "  New Large Files\n", &changes.new_large_files);
+        print_changed_files(&mut out, "  Deleted Large Files\n", &changes.deleted_files);
+        if changes.directories.is_empty() && changes.extensions.is_empty() {
+            let _ = writeln!(out, "  No changes.");
+            let _ = writeln!(out);
+        }
+    }
+
+    if let Some(ref id) = result.snapshot {
+        write_bold(&mut out, "  Saved snapshot: ");
+        let _ = writeln!(out, "{}", id);
+        let _ = writeln!(out);
+    }
+
+    if !result.errors.is_empty() {
+        write_colored(&mut out, "  Errors:\n", Color::Red);
+        for err in &result.errors {
+            let _ = writeln!(out, "    {}", err);
+        }
+        let _ = writeln!(out);
+    }
+}
+
+fn print_owners(out: &mut StandardStream, title: &str, column: &str, owners: &[OwnerStats]) {
+    write_colored(out, title, Color::Yellow);
+    let _ = writeln!(out, "  {:<20} {:>8} {:>12}", column, "Count", "Size");
+    let _ = writeln!(out, "  {}", "-".repeat(42));
+    for owner in owners {
+        let _ = writeln!(
+            out,
+            "  {:<20} {:>8} {:>12}",
+            owner.name,
+            owner.count,
+            format_size(owner.total_size)
+        );
+    }
+    let _ = writeln!(out);
+}
+
+/// Finish a heading line with the count and size, then list the files.
+fn print_audit_findings(out: &mut StandardStream, findings: &AuditFindings) {
+    let _ = writeln!(
+        out,
+        "{} ({})",
+        findings.count,
+        format_size(findings.total_size)
+    );
+    for entry in &findings.files {
+        let _ = writeln!(
+            out,
+            "    {} {:<12} {} ({})",
+            entry.mode,
+            entry.owner,
+            entry.path,
+            format_size(entry.size)
+        );
+    }
+    if findings.files.len() < findings.count {
+        let _ = writeln!(
+            out,
+            "    ... and {} more",
+            findings.count - findings.files.len()
+        );
+    }
+}
+
+/// Signed size, e.g. "+1.20 MB" or "-300 B".
+fn format_change(bytes: i64) -> String {
+    let sign = if bytes < 0 { "-" } else { "+" };
+    format!("{}{}", sign, format_size(bytes.unsigned_abs()))
+}
+
+fn print_growth(out: &mut StandardStream, title: &str, column: &str, changes: &[Growth]) {
+    if changes.is_empty() {
+        return;
+    }
+    write_colored(out, title, Color::Yellow);
+    let _ = writeln!(
+        out,
+        "  {:>12} {:>8} {:>12}  {}",
+        "Change", "Files", "Now", column
+    );
+    let _ = writeln!(out, "  {}", "-".repeat(50));
+    for growth in changes {
+        let _ = writeln!(
+            out,
+            "  {:>12} {:>+8} {:>12}  {}",
+            format_change(growth.change),
+            growth.after.files as i64 - growth.before.files as i64,
+            format_size(growth.after.size),
+            growth.name
+        );
+    }
+    let _ = writeln!(out);
+}
+
+fn print_changed_files(out: &mut StandardStream, title: &str, files: &[ChangedFile]) {
+    if files.is_empty() {
+        return;
+    }
+    write_colored(out, title, Color::Yellow);
+    for (i, file) in files.iter().enumerate() {
+        let _ = writeln!(
+            out,
+            "  {}. {} ({})",
+            i + 1,
+            file.path,
+            format_size(file.size)
+        );
+    }
+    let _ = writeln!(out);
+}
+
+/// One ASCII bar per bucket, scaled to the largest bucket by file count,
+/// or by bytes when `by_size` is set. Empty buckets at either end of the
+/// range are left out.
+fn print_histogram(out: &mut StandardStream, buckets: &[Bucket], by_size: bool) {
+    const WIDTH: u64 = 40;
+    let value = |b: &Bucket| if by_size { b.size } else { b.count as u64 };
+    let Some(first) = buckets.iter().position(|b| b.count > 0) else {
+        return;
+    };
+    let last = buckets.iter().rposition(|b| b.count > 0).unwrap_or(first);
+    let max = buckets.iter().map(value).max().unwrap_or(0).max(1);
+
+    for bucket in &buckets[first..=last] {
+        let len = (value(bucket) * WIDTH).div_ceil(max) as usize;
+        let _ = writeln!(
+            out,
+            "  {:<12} {:>8} {:>12}  {}",
+            bucket.label,
+            bucket.count,
+            format_size(bucket.size),
+            "#".repeat(len)
+        );
+    }
+}
+
+fn print_dir_node(out: &mut StandardStream, node: &DirNode, level: usize) {
+    // The root shows its full path, subdirectories just their name
+    let name = if level == 0 {
+        node.path.as_str()
+    } else {
+        node.path.rsplit(['/', '\\']).next().unwrap_or(&node.path)
+    };
+    let _ = writeln!(
+        out,
+        "  {:>12} {:>8}  {}{}",
+        format_size(node.size),
+        node.files,
+        "  ".repeat(level),
+        name
+    );
+    for child in &node.children {
+        print_dir_node(out, child, level + 1);
+    }
+}
+
+pub fn print_clean(result: &CleanResult) {
+    let mut out = StandardStream::stdout(ColorChoice::Auto);
+
+    if result.dry_run {
+        write_colored(&mut out, "\n  Clean Report\n", Color::Cyan);
+    } else {
+        write_colored(&mut out, "\n  Clean (moved to trash)\n", Color::Cyan);
+    }
+    let _ = writeln!(out);
+
+    write_bold(&mut out, "  Entries scanned: ");
+    let _ = writeln!(out, "{}", result.entries_scanned);
+    let _ = writeln!(out);
+
+    if !result.junk.is_empty() {
+        write_colored(
+            &mut out,
+            &format!(
+                "  Junk ({}, {})\n",
+                result.junk.len(),
+                format_size(result.junk_size)
+            ),
+            Color::Yellow,
+        );
+        for junk in &result.junk {
+            let _ = writeln!(
+                out,
+                "  {:>12}  {}  ({})",
+                format_size(junk.size),
+                junk.path,
+                junk.pattern
+            );
+        }
+        let _ = writeln!(out);
+    }
+
+    if !result.broken_symlinks.is_empty() {
+        write_colored(
+            &mut out,
+            &format!("  Broken Symlinks ({})\n", result.broken_symlinks.len()),
+            Color::Yellow,
+        );
+        for link in &result.broken_symlinks {
+            let _ = writeln!(out, "  {} → {}", link.path, link.target);
+        }
+        let _ = writeln!(out);
+    }
+
+    for (title, paths) in [
+        ("Empty Files", &result.empty_files),
+        ("Empty Directories", &result.empty_dirs),
+    ] {
+        if paths.is_empty() {
+            continue;
+        }
+        write_colored(
+            &mut out,
+            &format!("  {} ({})\n", title, paths.len()),
+            Color::Yellow,
+        );
+        for path in paths {
+            let _ = writeln!(out, "  {}", path);
+        }
+        let _ = writeln!(out);
+    }
+
+    if result.junk.is_empty()
+        && result.broken_symlinks.is_empty()
+        && result.empty_files.is_empty()
+        && result.empty_dirs.is_empty()
+    {
+        let _ = writeln!(out, "  Nothing to clean.");
+        let _ = writeln!(out);
+    } else if result.dry_run {
+        let _ = writeln!(out, "  Run with --trash to move these to fiq's trash.");
+        let _ = writeln!(out);
+    } else {
+        write_bold(&mut out, "  Moved to trash: ");
+        let _ = writeln!(out, "{}", result.trashed);
+    }
+
+    if !result.errors.is_empty() {
+        write_colored(&mut out, "  Errors:\n", Color::Red);
+        for err in &result.errors {
+            let _ = writeln!(out, "    {}", err);
+        }
+    }
+
+    if let Some(ref run_id) = result.run_id {
+        let _ = writeln!(out);
+        write_bold(&mut out, "  Undo with: ");
+        let _ = writeln!(out, "fiq undo {}", run_id);
+    }
+
+    let _ = writeln!(out);
+}
+
+pub fn print_cold(result: &ColdResult) {
+    let mut out = StandardStream::stdout(ColorChoice::Auto);
+
+    write_colored(&mut out, "\n  Cold Data\n", Color::Cyan);
+    let _ = writeln!(out);
+
+    write_bold(&mut out, "  Files scanned: ");
+    let _ = writeln!(out, "{}", result.files_scanned);
+    write_bold(&mut out, "  Cold files: ");
+    let _ = writeln!(
+        out,
+        "{} ({})",
+        result.cold_files,
+        format_size(result.total_size)
+    );
+    if result.by_access {
+        let _ = writeln!(out, "  Age counts from the last access or modification");
+    }
+    let _ = writeln!(out);
+
+    if !result.files.is_empty() {
+        write_colored(&mut out, "  Coldest Files (size × age)\n", Color::Yellow);
+        let _ = writeln!(
+            out,
+            "  {:>12} {:>10} {:>6}  Path",
+            "Size", "Last used", "Days"
+        );
+        let _ = writeln!(out, "  {}", "-".repeat(50));
+        for file in &result.files {
+            let _ = writeln!(
+                out,
+                "  {:>12} {:>10} {:>6}  {}",
+                format_size(file.size),
+                file.last_used,
+                file.age_days,
+                file.path
+            );
+        }
+        let _ = writeln!(out);
+    }
+
+    if !result.by_directory.is_empty() {
+        write_colored(&mut out, "  By Directory\n", Color::Yellow);
+        for dir in &result.by_directory {
+            let _ = writeln!(
+                out,
+                "  {:>12} {:>6} files  {}",
+                format_size(dir.size),
+                dir.files,
+                dir.path
             );
         }
         let _ = writeln!(out);
     }
+
+    if !result.errors.is_empty() {
+        write_colored(&mut out, "  Errors:\n", Color::Red);
+        for err in &result.errors {
+            let _ = writeln!(out, "    {}", err);
+        }
+        let _ = writeln!(out);
+    }
 }
 
 pub fn print_duplicates(result: &DuplicatesResult) {
@@ -95,7 +611,11 @@ pub fn print_duplicates(result: &DuplicatesResult) {
     write_bold(&mut out, "  Duplicate groups: ");
     let _ = writeln!(out, "{}", result.duplicate_groups.len());
 
-    write_bold(&mut out, "  Wasted space: ");
+    if result.disk_usage {
+        write_bold(&mut out, "  Wasted disk space: ");
+    } else {
+        write_bold(&mut out, "  Wasted space: ");
+    }
     let _ = writeln!(out, "{}", format_size(result.total_wasted_bytes));
     let _ = writeln!(out);
 
@@ -117,6 +637,219 @@ pub fn print_duplicates(result: &DuplicatesResult) {
     }
 }
 
+pub fn print_checksum_create(result: &ChecksumCreateResult) {
+    let mut out = StandardStream::stdout(ColorChoice::Auto);
+
+    write_colored(&mut out, "\n  Checksum Manifest\n", Color::Cyan);
+    let _ = writeln!(out);
+
+    if let Some(ref manifest) = result.manifest {
+        write_bold(&mut out, "  Manifest: ");
+        let _ = writeln!(out, "{}", manifest);
+    }
+
+    write_bold(&mut out, "  Algorithm: ");
+    let _ = writeln!(out, "{}", result.algorithm);
+
+    write_bold(&mut out, "  Files hashed: ");
+    let _ = writeln!(out, "{}", result.files_hashed);
+
+    write_bold(&mut out, "  Total size: ");
+    let _ = writeln!(out, "{}", format_size(result.total_size));
+
+    if !result.errors.is_empty() {
+        let _ = writeln!(out);
+        write_colored(&mut out, "  Errors:\n", Color::Red);
+        for err in &result.errors {
+            let _ = writeln!(out, "    {}", err);
+        }
+    }
+
+    let _ = writeln!(out);
+}
+
+pub fn print_checksum_verify(result: &ChecksumVerifyResult) {
+    let mut out = StandardStream::stdout(ColorChoice::Auto);
+
+    if result.passed() {
+        write_colored(&mut out, "\n  Checksum Verify: OK\n", Color::Cyan);
+    } else {
+        write_colored(&mut out, "\n  Checksum Verify: FAILED\n", Color::Red);
+    }
+    let _ = writeln!(out);
+
+    write_bold(&mut out, "  Manifest: ");
+    let _ = writeln!(out, "{} ({})", result.manifest, result.algorithm);
+
+    write_bold(&mut out, "  OK: ");
+    let _ = writeln!(out, "{}", result.ok);
+    write_bold(&mut out, "  Changed: ");
+    let _ = writeln!(out, "{}", result.changed.len());
+    write_bold(&mut out, "  Missing: ");
+    let _ = writeln!(out, "{}", result.missing.len());
+    write_bold(&mut out, "  New: ");
+    let _ = writeln!(out, "{}", result.new.len());
+    let _ = writeln!(out);
+
+    for (title, paths, color) in [
+        ("  Changed\n", &result.changed, Color::Red),
+        ("  Missing\n", &result.missing, Color::Red),
+        ("  New\n", &result.new, Color::Green),
+        ("  Errors\n", &result.errors, Color::Red),
+    ] {
+        if paths.is_empty() {
+            continue;
+        }
+        write_colored(&mut out, title, color);
+        for path in paths {
+            let _ = writeln!(out, "    {}", path);
+        }
+        let _ = writeln!(out);
+    }
+}
+
+pub fn print_diff(result: &DiffResult) {
+    let mut out = StandardStream::stdout(ColorChoice::Auto);
+
+    write_colored(&mut out, "\n  Directory Diff\n", Color::Cyan);
+    let _ = writeln!(out);
+
+    write_bold(&mut out, "  Left:  ");
+    let _ = writeln!(out, "{}", result.left);
+    write_bold(&mut out, "  Right: ");
+    let _ = writeln!(out, "{}", result.right);
+    let _ = writeln!(out);
+
+    write_bold(&mut out, "  Same: ");
+    let _ = writeln!(out, "{}", result.same);
+    write_bold(&mut out, "  Modified: ");
+    let _ = writeln!(out, "{}", result.modified.len());
+    write_bold(&mut out, "  Moved: ");
+    let _ = writeln!(out, "{}", result.moved.len());
+    write_bold(&mut out, "  Only in left: ");
+    let _ = writeln!(out, "{}", result.only_left.len());
+    write_bold(&mut out, "  Only in right: ");
+    let _ = writeln!(out, "{}", result.only_right.len());
+    let _ = writeln!(out);
+
+    if !result.modified.is_empty() {
+        write_colored(&mut out, "  Modified\n", Color::Yellow);
+        for m in &result.modified {
+            let _ = writeln!(
+                out,
+                "    {} ({} → {}, {})",
+                m.path,
+                format_size(m.left_size),
+                format_size(m.right_size),
+                m.reason
+            );
+        }
+        let _ = writeln!(out);
+    }
+
+    if !result.moved.is_empty() {
+        write_colored(&mut out, "  Moved\n", Color::Yellow);
+        for m in &result.moved {
+            let _ = writeln!(out, "    {} → {}  ({})", m.from, m.to, format_size(m.size));
+        }
+        let _ = writeln!(out);
+    }
+
+    for (title, entries, color) in [
+        ("  Only in left\n", &result.only_left, Color::Red),
+        ("  Only in right\n", &result.only_right, Color::Green),
+    ] {
+        if entries.is_empty() {
+            continue;
+        }
+        write_colored(&mut out, title, color);
+        for e in entries {
+            let _ = writeln!(out, "    {} ({})", e.path, format_size(e.size));
+        }
+        let _ = writeln!(out);
+    }
+}
+
+pub fn print_find_copies(result: &FindCopiesResult) {
+    let mut out = StandardStream::stdout(ColorChoice::Auto);
+
+    write_colored(&mut out, "\n  Find Copies\n", Color::Cyan);
+    let _ = writeln!(out);
+
+    write_bold(&mut out, "  Hash: ");
+    let _ = writeln!(out, "{}", result.hash);
+
+    if result.from_cache {
+        write_bold(&mut out, "  Answered from hash cache\n");
+    } else {
+        write_bold(&mut out, "  Files scanned: ");
+        let _ = writeln!(out, "{}", result.files_scanned);
+
+        write_bold(&mut out, "  Files hashed: ");
+        let _ = writeln!(out, "{}", result.files_hashed);
+    }
+
+    write_bold(&mut out, "  Copies found: ");
+    let _ = writeln!(out, "{}", result.matches.len());
+    let _ = writeln!(out);
+
+    for m in &result.matches {
+        write_colored(&mut out, &format!("  {}", m.path), Color::Green);
+        let _ = writeln!(out, "  ({})", format_size(m.size));
+    }
+
+    if !result.errors.is_empty() {
+        let _ = writeln!(out);
+        write_colored(&mut out, "  Errors:\n", Color::Red);
+        for err in &result.errors {
+            let _ = writeln!(out, "    {}", err);
+        }
+    }
+
+    let _ = writeln!(out);
+}
+
+pub fn print_similar(result: &SimilarResult) {
+    let mut out = StandardStream::stdout(ColorChoice::Auto);
+
+    write_colored(&mut out, "\n  Similar Files\n", Color::Cyan);
+    let _ = writeln!(out);
+
+    write_bold(&mut out, "  Files scanned: ");
+    let _ = writeln!(out, "{}", result.total_files_scanned);
+
+    write_bold(&mut out, "  Files compared: ");
+    let _ = writeln!(out, "{}", result.files_compared);
+
+    write_bold(&mut out, "  Similar groups: ");
+    let _ = writeln!(out, "{}", result.similar_groups.len());
+    let _ = writeln!(out);
+
+    for (i, group) in result.similar_groups.iter().enumerate() {
+        write_colored(
+            &mut out,
+            &format!(
+                "  Group {} ({} {} files, {})\n",
+                i + 1,
+                group.files.len(),
+                group.kind,
+                format_size(group.total_size)
+            ),
+            Color::Yellow,
+        );
+        for file in &group.files {
+            let _ = writeln!(
+                out,
+                "    {:>5.1}%  {} ({})",
+                file.similarity * 100.0,
+                file.path,
+                format_size(file.size)
+            );
+        }
+        let _ = writeln!(out);
+    }
+}
+
 pub fn print_search(result: &SearchResult) {
     let mut out = StandardStream::stdout(ColorChoice::Auto);
 
@@ -151,6 +884,12 @@ pub fn print_organize(result: &OrganizeResult) {
 
     if result.dry_run {
         write_colored(&mut out, "\n  Organize Preview (dry run)\n", Color::Cyan);
+    } else if result.rolled_back {
+        write_colored(
+            &mut out,
+            "\n  Organize Failed (completed steps rolled back)\n",
+            Color::Red,
+        );
     } else {
         write_colored(&mut out, "\n  Organize Complete\n", Color::Cyan);
     }
@@ -159,7 +898,7 @@ pub fn print_organize(result: &OrganizeResult) {
     write_bold(&mut out, "  Total files: ");
     let _ = writeln!(out, "{}", result.total_files);
 
-    write_bold(&mut out, "  Files to move: ");
+    write_bold(&mut out, &format!("  Files to {}: ", result.action));
     let _ = writeln!(out, "{}", result.moves.len());
     let _ = writeln!(out);
 
@@ -171,6 +910,147 @@ pub fn print_organize(result: &OrganizeResult) {
         let _ = writeln!(out, "  ({})", format_size(m.size));
     }
 
+    if !result.identical.is_empty() {
+        let _ = writeln!(out);
+        if result.action == "move" {
+            write_bold(&mut out, "  Identical to destination (source removed): ");
+        } else {
+            write_bold(&mut out, "  Identical to destination (skipped): ");
+        }
+        let _ = writeln!(out, "{}", result.identical.len());
+        for m in &result.identical {
+            let _ = write!(out, "  ");
+            write_colored(&mut out, &m.from, Color::Red);
+            let _ = write!(out, " = ");
+            write_colored(&mut out, &m.to, Color::Green);
+            let _ = writeln!(out, "  ({})", format_size(m.size));
+        }
+    }
+
+    if !result.pruned.is_empty() {
+        let _ = writeln!(out);
+        write_bold(&mut out, "  Removed empty folders: ");
+        let _ = writeln!(out, "{}", result.pruned.len());
+        for dir in &result.pruned {
+            let _ = writeln!(out, "  {}", dir);
+        }
+    }
+
+    if !result.errors.is_empty() {
+        let _ = writeln!(out);
+        write_colored(&mut out, "  Errors:\n", Color::Red);
+        for err in &result.errors {
+            let _ = writeln!(out, "    {}", err);
+        }
+    }
+
+    if let Some(ref run_id) = result.run_id {
+        let _ = writeln!(out);
+        write_bold(&mut out, "  Undo with: ");
+        let _ = writeln!(out, "fiq undo {}", run_id);
+    }
+
+    let _ = writeln!(out);
+}
+
+pub fn print_rename(result: &RenameResult) {
+    let mut out = StandardStream::stdout(ColorChoice::Auto);
+
+    if result.dry_run {
+        write_colored(&mut out, "\n  Rename Preview (dry run)\n", Color::Cyan);
+    } else {
+        write_colored(&mut out, "\n  Rename Complete\n", Color::Cyan);
+    }
+    let _ = writeln!(out);
+
+    write_bold(&mut out, "  Matching files: ");
+    let _ = writeln!(out, "{}", result.matched);
+
+    write_bold(&mut out, "  Files to rename: ");
+    let _ = writeln!(out, "{}", result.renames.len());
+    let _ = writeln!(out);
+
+    for m in &result.renames {
+        let _ = write!(out, "  ");
+        write_colored(&mut out, &m.from, Color::Red);
+        let _ = write!(out, " → ");
+        write_colored(&mut out, &m.to, Color::Green);
+        let _ = writeln!(out);
+    }
+
+    if !result.conflicts.is_empty() {
+        let _ = writeln!(out);
+        write_colored(&mut out, "  Conflicts (nothing renamed):\n", Color::Yellow);
+        for conflict in &result.conflicts {
+            let _ = writeln!(out, "    {}", conflict);
+        }
+    }
+
+    if !result.errors.is_empty() {
+        let _ = writeln!(out);
+        write_colored(&mut out, "  Errors:\n", Color::Red);
+        for err in &result.errors {
+            let _ = writeln!(out, "    {}", err);
+        }
+    }
+
+    if result.dry_run && !result.renames.is_empty() && result.conflicts.is_empty() {
+        let _ = writeln!(out);
+        let _ = writeln!(out, "  Preview only; rerun with --apply to rename");
+    }
+    if let Some(ref run_id) = result.run_id {
+        let _ = writeln!(out);
+        write_bold(&mut out, "  Undo with: ");
+        let _ = writeln!(out, "fiq undo {}", run_id);
+    }
+
+    let _ = writeln!(out);
+}
+
+pub fn print_undo(result: &UndoResult) {
+    let mut out = StandardStream::stdout(ColorChoice::Auto);
+
+    if result.dry_run {
+        write_colored(&mut out, "\n  Undo Preview (dry run)\n", Color::Cyan);
+    } else {
+        write_colored(&mut out, "\n  Undo Complete\n", Color::Cyan);
+    }
+    let _ = writeln!(out);
+
+    if !result.run_id.is_empty() {
+        write_bold(&mut out, "  Run: ");
+        let _ = writeln!(out, "{}", result.run_id);
+    }
+
+    write_bold(&mut out, "  Files restored: ");
+    let _ = writeln!(out, "{}", result.restored.len());
+    let _ = writeln!(out);
+
+    for m in &result.restored {
+        let _ = write!(out, "  ");
+        write_colored(&mut out, &m.from, Color::Red);
+        let _ = write!(out, " → ");
+        write_colored(&mut out, &m.to, Color::Green);
+        let _ = writeln!(out, "  ({})", format_size(m.size));
+    }
+
+    for (title, paths) in [
+        ("  Copies and links removed: ", &result.removed),
+        ("  Directories removed: ", &result.removed_dirs),
+    ] {
+        if paths.is_empty() {
+            continue;
+        }
+        let _ = writeln!(out);
+        write_bold(&mut out, title);
+        let _ = writeln!(out, "{}", paths.len());
+        for path in paths {
+            let _ = write!(out, "  ");
+            write_colored(&mut out, path, Color::Red);
+            let _ = writeln!(out);
+        }
+    }
+
     if !result.errors.is_empty() {
         let _ = writeln!(out);
         write_colored(&mut out, "  Errors:\n", Color::Red);
@@ -181,3 +1061,23 @@ pub fn print_organize(result: &OrganizeResult) {
 
     let _ = writeln!(out);
 }
+
+pub fn print_undo_runs(runs: &[String]) {
+    let mut out = StandardStream::stdout(ColorChoice::Auto);
+
+    write_colored(
+        &mut out,
+        "\n  Organize, Rename and Clean Runs\n",
+        Color::Cyan,
+    );
+    let _ = writeln!(out);
+
+    if runs.is_empty() {
+        let _ = writeln!(out, "  Nothing to undo");
+    }
+    for run in runs {
+        let _ = writeln!(out, "  {}", run);
+    }
+
+    let _ = writeln!(out);
+}
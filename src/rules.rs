@@ -0,0 +1,290 @@
+use std::path::{Component, Path, PathBuf};
+use std::time::SystemTime;
+
+use globset::{Glob, GlobMatcher};
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::commands::search::{parse_size, parse_time};
+use crate::scanner::FileInfo;
+
+/// A rules file as written by the user.
+///
+/// ```toml
+/// fallback = "Other"
+///
+/// [[rule]]
+/// dest = "Screenshots"
+/// name_regex = "^Screen ?[Ss]hot"
+///
+/// [[rule]]
+/// dest = "Photos/Large"
+/// extensions = ["jpg", "heic"]
+/// min_size = "5MB"
+/// ```
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RulesFile {
+    fallback: Option<String>,
+    #[serde(default, rename = "rule")]
+    rules: Vec<RuleSpec>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RuleSpec {
+    dest: String,
+    glob: Option<String>,
+    extensions: Option<Vec<String>>,
+    min_size: Option<String>,
+    max_size: Option<String>,
+    /// Modified before this time ("180d", "2024-01-01")
+    older: Option<String>,
+    /// Modified after this time
+    newer: Option<String>,
+    name_regex: Option<String>,
+}
+
+/// A compiled rule. Every condition that is set must match.
+struct Rule {
+    dest: String,
+    glob: Option<GlobMatcher>,
+    extensions: Option<Vec<String>>,
+    min_size: Option<u64>,
+    max_size: Option<u64>,
+    older: Option<SystemTime>,
+    newer: Option<SystemTime>,
+    name_regex: Option<Regex>,
+}
+
+/// Ordered destination rules for organize. The first matching rule wins;
+/// files matching none go to the fallback folder.
+pub struct RuleSet {
+    rules: Vec<Rule>,
+    fallback: String,
+}
+
+/// Default rules file locations, in lookup order.
+fn default_paths() -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    if let Some(dir) = dirs::config_dir() {
+        paths.push(dir.join("fiq").join("rules.toml"));
+    }
+    // macOS config_dir() is ~/Library/Application Support; honor ~/.config too
+    if let Some(home) = dirs::home_dir() {
+        let xdg = home.join(".config").join("fiq").join("rules.toml");
+        if !paths.contains(&xdg) {
+            paths.push(xdg);
+        }
+    }
+    paths
+}
+
+/// Destinations are folders under the output directory: relative, no `..`.
+fn validate_dest(dest: &str) -> Result<(), String> {
+    let path = Path::new(dest);
+    if dest.is_empty()
+        || !path
+            .components()
+            .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+    {
+        return Err(format!(
+            "Invalid destination \"{}\": must be a relative folder without \"..\"",
+            dest
+        ));
+    }
+    Ok(())
+}
+
+impl RuleSet {
+    /// Load rules from `path`, or from the first default location that exists.
+    pub fn load(path: Option<&str>) -> Result<RuleSet, String> {
+        let path = match path {
+            Some(p) => PathBuf::from(p),
+            None => default_paths()
+                .into_iter()
+                .find(|p| p.is_file())
+                .ok_or_else(|| {
+                    "No rules file found (looked for ~/.config/fiq/rules.toml); pass --rules <path>"
+                        .to_string()
+                })?,
+        };
+        let text = std::fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        Self::parse(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    /// Parse and compile rules from TOML text.
+    pub fn parse(text: &str) -> Result<RuleSet, String> {
+        let file: RulesFile = toml::from_str(text).map_err(|e| e.to_string())?;
+
+        let fallback = file.fallback.unwrap_or_else(|| "Other".to_string());
+        validate_dest(&fallback)?;
+
+        let mut rules = Vec::with_capacity(file.rules.len());
+        for (i, spec) in file.rules.into_iter().enumerate() {
+            let n = i + 1;
+            validate_dest(&spec.dest).map_err(|e| format!("rule {}: {}", n, e))?;
+
+            let glob = spec
+                .glob
+                .map(|g| Glob::new(&g).map(|g| g.compile_matcher()))
+                .transpose()
+                .map_err(|e| format!("rule {}: invalid glob: {}", n, e))?;
+            let name_regex = spec
+                .name_regex
+                .map(|r| Regex::new(&r))
+                .transpose()
+                .map_err(|e| format!("rule {}: invalid regex: {}", n, e))?;
+            let size = |s: Option<String>, field: &str| {
+                s.map(|s| {
+                    parse_size(&s).ok_or_else(|| format!("rule {}: invalid {}: {}", n, field, s))
+                })
+                .transpose()
+            };
+            let time = |s: Option<String>, field: &str| {
+                s.map(|s| {
+                    parse_time(&s).ok_or_else(|| format!("rule {}: invalid {}: {}", n, field, s))
+                })
+                .transpose()
+            };
+
+            rules.push(Rule {
+                dest: spec.dest,
+                glob,
+                extensions: spec.extensions.map(|exts| {
+                    exts.iter()
+                        .map(|e| e.trim_start_matches('.').to_lowercase())
+                        .collect()
+                }),
+                min_size: size(spec.min_size, "min_size")?,
+                max_size: size(spec.max_size, "max_size")?,
+                older: time(spec.older, "older")?,
+                newer: time(spec.newer, "newer")?,
+                name_regex,
+            });
+        }
+
+        Ok(RuleSet { rules, fallback })
+    }
+
+    /// First path component of every destination, fallback included.
+    pub fn top_folders(&self) -> impl Iterator<Item = &str> {
+        self.rules
+            .iter()
+            .map(|r| r.dest.as_str())
+            .chain(std::iter::once(self.fallback.as_str()))
+            .filter_map(|d| {
+                Path::new(d)
+                    .components()
+                    .find(|c| matches!(c, Component::Normal(_)))
+                    .and_then(|c| c.as_os_str().to_str())
+            })
+    }
+
+    /// Destination folder (relative to the output directory) for a file.
+    pub fn categorize(&self, file: &FileInfo) -> &str {
+        let name = file.path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+        let ext = file.extension.as_deref().unwrap_or("");
+
+        self.rules
+            .iter()
+            .find(|rule| rule.matches(name, ext, file))
+            .map(|rule| rule.dest.as_str())
+            .unwrap_or(&self.fallback)
+    }
+}
+
+impl Rule {
+    fn matches(&self, name: &str, ext: &str, file: &FileInfo) -> bool {
+        if let Some(ref glob) = self.glob
+            && !glob.is_match(name)
+        {
+            return false;
+        }
+        if let Some(ref exts) = self.extensions
+            && !exts.iter().any(|e| e == ext)
+        {
+            return false;
+        }
+        if self.min_size.is_some_and(|min| file.size < min)
+            || self.max_size.is_some_and(|max| file.size > max)
+        {
+            return false;
+        }
+        if let Some(older) = self.older
+            && file.modified.is_none_or(|m| m > older)
+        {
+            return false;
+        }
+        if let Some(newer) = self.newer
+            && file.modified.is_none_or(|m| m < newer)
+        {
+            return false;
+        }
+        if let Some(ref re) = self.name_regex
+            && !re.is_match(name)
+        {
+            return false;
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn file(name: &str, size: u64, age_days: u64) -> FileInfo {
+        let modified = SystemTime::now().checked_sub(Duration::from_secs(age_days * 86400));
+        FileInfo::test_file(name, size, modified)
+    }
+
+    #[test]
+    fn test_first_matching_rule_wins() {
+        let rules = RuleSet::parse(
+            r#"
+            fallback = "Misc"
+
+            [[rule]]
+            dest = "Screenshots"
+            name_regex = "^Screen ?[Ss]hot"
+
+            [[rule]]
+            dest = "Photos/Large"
+            extensions = [".JPG", "png"]
+            min_size = "1MB"
+
+            [[rule]]
+            dest = "Photos"
+            glob = "*.{jpg,png}"
+
+            [[rule]]
+            dest = "Archive"
+            older = "365d"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            rules.categorize(&file("Screenshot 1.png", 5_000_000, 1)),
+            "Screenshots"
+        );
+        assert_eq!(
+            rules.categorize(&file("IMG_1.jpg", 5_000_000, 1)),
+            "Photos/Large"
+        );
+        assert_eq!(rules.categorize(&file("IMG_2.jpg", 10, 1)), "Photos");
+        assert_eq!(rules.categorize(&file("report.pdf", 10, 400)), "Archive");
+        assert_eq!(rules.categorize(&file("report.pdf", 10, 3)), "Misc");
+    }
+
+    #[test]
+    fn test_invalid_rules_rejected() {
+        assert!(RuleSet::parse("[[rule]]\ndest = \"../escape\"\n").is_err());
+        assert!(RuleSet::parse("[[rule]]\ndest = \"A\"\nmin_size = \"lots\"\n").is_err());
+        assert!(RuleSet::parse("[[rule]]\ndest = \"A\"\nname_regex = \"(\"\n").is_err());
+        assert!(RuleSet::parse("[[rule]]\ndest = \"A\"\ntypo = 1\n").is_err());
+    }
+}
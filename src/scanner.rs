@@ -14,10 +14,87 @@ const BATCH_SIZE: usize = 512;
 #[derive(Debug, Clone, Serialize)]
 pub struct FileInfo {
     pub path: PathBuf,
+    /// Apparent size (`metadata.len()`)
     pub size: u64,
+    /// Space allocated on disk: `st_blocks * 512` on Unix, the apparent
+    /// size elsewhere. Smaller than `size` for sparse and compressed files.
+    pub allocated: u64,
     pub modified: Option<SystemTime>,
+    /// Directories and unfollowed symlinks are only reported by
+    /// `scan_entries`; other scans skip directories and follow symlinks
     pub is_dir: bool,
+    pub is_symlink: bool,
     pub extension: Option<String>,
+    /// Owner and group ids and permission bits (including setuid, setgid
+    /// and sticky); all 0 where the platform has no Unix permissions
+    pub uid: u32,
+    pub gid: u32,
+    pub mode: u32,
+}
+
+/// Bytes a file occupies on disk.
+#[cfg(unix)]
+pub fn allocated_size(metadata: &std::fs::Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    metadata.blocks() * 512
+}
+
+/// Bytes a file occupies on disk.
+#[cfg(not(unix))]
+pub fn allocated_size(metadata: &std::fs::Metadata) -> u64 {
+    metadata.len()
+}
+
+/// Owner id, group id and permission bits of a file.
+#[cfg(unix)]
+pub fn ownership(metadata: &std::fs::Metadata) -> (u32, u32, u32) {
+    use std::os::unix::fs::MetadataExt;
+    (metadata.uid(), metadata.gid(), metadata.mode() & 0o7777)
+}
+
+/// Owner id, group id and permission bits of a file.
+#[cfg(not(unix))]
+pub fn ownership(_metadata: &std::fs::Metadata) -> (u32, u32, u32) {
+    (0, 0, 0)
+}
+
+impl FileInfo {
+    /// Size in the chosen measure: allocated or apparent.
+    pub fn size_in(&self, disk_usage: bool) -> u64 {
+        if disk_usage {
+            self.allocated
+        } else {
+            self.size
+        }
+    }
+
+    /// Whether less space is allocated than the file's length, as for
+    /// sparse files and files on compressing file systems.
+    pub fn is_sparse(&self) -> bool {
+        self.allocated < self.size
+    }
+
+    /// A regular file that was never read from disk, for unit tests. The
+    /// extension comes from the path; fully allocated, owned by root, 0644.
+    #[cfg(test)]
+    pub fn test_file(path: impl Into<PathBuf>, size: u64, modified: Option<SystemTime>) -> Self {
+        let path = path.into();
+        FileInfo {
+            extension: path
+                .extension()
+                .and_then(|e| e.to_str())
+                .map(|e| e.to_lowercase()),
+            path,
+            size,
+            allocated: size,
+            modified,
+            is_dir: false,
+            is_symlink: false,
+            uid: 0,
+            gid: 0,
+            mode: 0o644,
+        }
+    }
 }
 
 /// Thread count for the I/O-bound directory walker.
@@ -73,7 +150,7 @@ pub fn scan_directory_filtered(
     recursive: bool,
     name_glob: Option<&str>,
 ) -> Vec<FileInfo> {
-    scan_directory_impl(dir, recursive, name_glob, false)
+    scan_directory_impl(dir, recursive, name_glob, false, false)
 }
 
 /// Walk a directory, skipping metadata collection for maximum speed.
@@ -83,7 +160,14 @@ pub fn scan_directory_names_only(
     recursive: bool,
     name_glob: Option<&str>,
 ) -> Vec<FileInfo> {
-    scan_directory_impl(dir, recursive, name_glob, true)
+    scan_directory_impl(dir, recursive, name_glob, true, false)
+}
+
+/// Walk a directory, reporting every entry below it as itself:
+/// subdirectories with `is_dir` set and symlinks, broken or not, with
+/// `is_symlink` set and their own metadata. Used by clean.
+pub fn scan_entries(dir: &Path, recursive: bool) -> Vec<FileInfo> {
+    scan_directory_impl(dir, recursive, None, false, true)
 }
 
 fn scan_directory_impl(
@@ -91,6 +175,7 @@ fn scan_directory_impl(
     recursive: bool,
     name_glob: Option<&str>,
     skip_metadata: bool,
+    all_entries: bool,
 ) -> Vec<FileInfo> {
     let files = Arc::new(Mutex::new(Vec::with_capacity(if name_glob.is_some() {
         256
@@ -159,7 +244,11 @@ fn scan_directory_impl(
             };
 
             // file_type() comes from readdir — no stat() syscall
-            if entry.file_type().is_none_or(|ft| ft.is_dir()) {
+            let Some(file_type) = entry.file_type() else {
+                return WalkState::Continue;
+            };
+            // The root itself is never reported
+            if file_type.is_dir() && !(all_entries && entry.depth() > 0) {
                 return WalkState::Continue;
             }
 
@@ -181,20 +270,29 @@ fn scan_directory_impl(
                 collector.push(FileInfo {
                     path,
                     size: 0,
+                    allocated: 0,
                     modified: None,
                     is_dir: false,
+                    is_symlink: false,
                     extension: None,
+                    uid: 0,
+                    gid: 0,
+                    mode: 0,
                 });
             } else {
                 // metadata() only for files that passed all cheap filters
-                let metadata = match std::fs::metadata(&path) {
-                    Ok(m) => m,
-                    Err(_) => return WalkState::Continue,
+                let metadata = if all_entries {
+                    std::fs::symlink_metadata(&path)
+                } else {
+                    std::fs::metadata(&path)
+                };
+                let Ok(metadata) = metadata else {
+                    return WalkState::Continue;
                 };
 
                 // Skip extension computation for filtered scans — search
                 // never uses it, saves a String allocation per match
-                let extension = if is_filtered {
+                let extension = if is_filtered || file_type.is_dir() {
                     None
                 } else {
                     path.extension()
@@ -202,12 +300,18 @@ fn scan_directory_impl(
                         .map(|e| e.to_lowercase())
                 };
 
+                let (uid, gid, mode) = ownership(&metadata);
                 collector.push(FileInfo {
                     path,
                     size: metadata.len(),
+                    allocated: allocated_size(&metadata),
                     modified: metadata.modified().ok(),
-                    is_dir: false,
+                    is_dir: file_type.is_dir(),
+                    is_symlink: all_entries && file_type.is_symlink(),
                     extension,
+                    uid,
+                    gid,
+                    mode,
                 });
             }
 
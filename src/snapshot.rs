@@ -0,0 +1,349 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::scanner::FileInfo;
+
+/// Files at least this large are listed individually in a snapshot, so
+/// comparisons can name new and deleted large files. Smaller files only
+/// count towards the directory and extension totals.
+pub const LARGE_FILE: u64 = 1_000_000;
+
+/// Snapshots kept per root; saving another removes the oldest.
+pub const MAX_SNAPSHOTS: usize = 20;
+
+/// File count and bytes.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Tally {
+    pub files: usize,
+    pub size: u64,
+}
+
+impl Tally {
+    fn add(&mut self, size: u64) {
+        self.files += 1;
+        self.size += size;
+    }
+}
+
+/// Compact summary of a directory tree at one point in time. Paths are
+/// relative to `root` and use `/`; the root itself is `.`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub id: String,
+    pub root: PathBuf,
+    pub taken_at: String,
+    /// Sizes are allocated disk space rather than apparent size
+    pub disk_usage: bool,
+    pub total: Tally,
+    /// Cumulative totals per directory, including subdirectories
+    pub dirs: BTreeMap<String, Tally>,
+    pub extensions: BTreeMap<String, Tally>,
+    /// Files of at least `LARGE_FILE` bytes and their sizes
+    pub large_files: BTreeMap<String, u64>,
+}
+
+/// Change in one directory or extension between two snapshots.
+#[derive(Debug, Serialize)]
+pub struct Growth {
+    pub name: String,
+    pub before: Tally,
+    pub after: Tally,
+    /// Bytes added, negative when it shrank
+    pub change: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ChangedFile {
+    pub path: String,
+    pub size: u64,
+}
+
+/// What changed between an earlier snapshot and the current tree.
+#[derive(Debug, Serialize)]
+pub struct SnapshotDiff {
+    /// Id of the earlier snapshot
+    pub snapshot: String,
+    pub taken_at: String,
+    pub disk_usage: bool,
+    pub before: Tally,
+    pub after: Tally,
+    /// Directories that changed, largest change first
+    pub directories: Vec<Growth>,
+    /// Extensions that changed, largest change first
+    pub extensions: Vec<Growth>,
+    /// Large files that were not large files in the earlier snapshot
+    pub new_large_files: Vec<ChangedFile>,
+    /// Large files from the earlier snapshot that no longer exist. Files
+    /// that only shrank below `LARGE_FILE` are not listed.
+    pub deleted_files: Vec<ChangedFile>,
+}
+
+/// Snapshot directory for a root: ~/.cache/fiq/snapshots/<key>/
+fn snapshot_dir(root: &Path) -> Option<PathBuf> {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+    let mut hasher = DefaultHasher::new();
+    root.hash(&mut hasher);
+    dirs::cache_dir().map(|d| {
+        d.join("fiq")
+            .join("snapshots")
+            .join(format!("{:016x}", hasher.finish()))
+    })
+}
+
+fn relative_key(path: &Path) -> String {
+    let parts: Vec<_> = path
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect();
+    if parts.is_empty() {
+        ".".to_string()
+    } else {
+        parts.join("/")
+    }
+}
+
+/// Snapshot files in `dir`, oldest first. Ids sort chronologically, but
+/// `X-1.snap` sorts before `X.snap`, so they are compared without `.snap`.
+fn saved_snapshots(dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut saved: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|e| e == "snap"))
+        .collect();
+    saved.sort_by(|a, b| a.file_stem().cmp(&b.file_stem()));
+    Ok(saved)
+}
+
+impl Snapshot {
+    /// Summarize `files`, as scanned from `dir`.
+    pub fn take(dir: &Path, files: &[FileInfo], disk_usage: bool) -> Self {
+        let mut snapshot = Snapshot {
+            id: chrono::Local::now().format("%Y%m%d-%H%M%S-%3f").to_string(),
+            root: dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf()),
+            taken_at: crate::journal::now_timestamp(),
+            disk_usage,
+            total: Tally::default(),
+            dirs: BTreeMap::new(),
+            extensions: BTreeMap::new(),
+            large_files: BTreeMap::new(),
+        };
+
+        for file in files {
+            let size = file.size_in(disk_usage);
+            let rel = file.path.strip_prefix(dir).unwrap_or(&file.path);
+            snapshot.total.add(size);
+            let mut parent = rel.parent();
+            while let Some(p) = parent {
+                snapshot.dirs.entry(relative_key(p)).or_default().add(size);
+                parent = p.parent();
+            }
+            let ext = file
+                .extension
+                .clone()
+                .unwrap_or_else(|| "(no ext)".to_string());
+            snapshot.extensions.entry(ext).or_default().add(size);
+            if size >= LARGE_FILE {
+                snapshot.large_files.insert(relative_key(rel), size);
+            }
+        }
+        snapshot
+    }
+
+    /// Write the snapshot to the cache directory, removing the oldest
+    /// snapshots of the same root beyond `MAX_SNAPSHOTS`. A snapshot saved
+    /// in the same millisecond as another gets a suffixed id.
+    pub fn save(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let dir = snapshot_dir(&self.root).ok_or("no cache dir")?;
+        std::fs::create_dir_all(&dir)?;
+        let (id, mut file) = crate::journal::create_unique(&dir, &self.id, ".snap")?;
+        self.id = id;
+        file.write_all(&bincode::serialize(self)?)?;
+
+        for old in saved_snapshots(&dir)?.iter().rev().skip(MAX_SNAPSHOTS) {
+            std::fs::remove_file(old)?;
+        }
+        Ok(())
+    }
+
+    /// Load a snapshot of `dir`: `last` for the most recent one, a
+    /// snapshot id, or the path of a snapshot file.
+    pub fn load(dir: &Path, which: &str) -> Result<Self, String> {
+        let root = dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf());
+        let snapshots = snapshot_dir(&root).ok_or("No cache directory")?;
+        let path = if which == "last" {
+            saved_snapshots(&snapshots)
+                .ok()
+                .and_then(|mut saved| saved.pop())
+                .ok_or_else(|| format!("No snapshots of {}", root.display()))?
+        } else if Path::new(which).is_file() {
+            PathBuf::from(which)
+        } else {
+            snapshots.join(format!("{}.snap", which))
+        };
+
+        let bytes =
+            std::fs::read(&path).map_err(|e| format!("Cannot read snapshot {}: {}", which, e))?;
+        let snapshot: Snapshot = bincode::deserialize(&bytes)
+            .map_err(|e| format!("Invalid snapshot {}: {}", path.display(), e))?;
+        if snapshot.root != root {
+            return Err(format!(
+                "Snapshot {} is of {}, not {}",
+                snapshot.id,
+                snapshot.root.display(),
+                root.display()
+            ));
+        }
+        Ok(snapshot)
+    }
+
+    /// Compare an earlier snapshot with this one, which must be of the tree
+    /// as it is on disk now. Directories are listed down to `depth` levels
+    /// below the root; directories and file lists are capped at `top_n`
+    /// entries.
+    pub fn diff_from(&self, old: &Snapshot, depth: usize, top_n: usize) -> SnapshotDiff {
+        let growth = |before: &BTreeMap<String, Tally>,
+                      after: &BTreeMap<String, Tally>,
+                      keep: &dyn Fn(&str) -> bool| {
+            let names: BTreeSet<&String> = before.keys().chain(after.keys()).collect();
+            let mut changes: Vec<Growth> = names
+                .into_iter()
+                .filter(|name| keep(name))
+                .filter_map(|name| {
+                    let before = before.get(name).copied().unwrap_or_default();
+                    let after = after.get(name).copied().unwrap_or_default();
+                    (before != after).then(|| Growth {
+                        name: name.clone(),
+                        before,
+                        after,
+                        change: after.size as i64 - before.size as i64,
+                    })
+                })
+                .collect();
+            changes.sort_by(|a, b| {
+                b.change
+                    .unsigned_abs()
+                    .cmp(&a.change.unsigned_abs())
+                    .then_with(|| a.name.cmp(&b.name))
+            });
+            changes
+        };
+        let level = |name: &str| {
+            if name == "." {
+                0
+            } else {
+                name.split('/').count()
+            }
+        };
+
+        let mut directories = growth(&old.dirs, &self.dirs, &|name| level(name) <= depth);
+        directories.truncate(top_n);
+        let extensions = growth(&old.extensions, &self.extensions, &|_| true);
+
+        let files_missing_from = |from: &BTreeMap<String, u64>,
+                                  other: &BTreeMap<String, u64>,
+                                  keep: &dyn Fn(&str) -> bool| {
+            let mut files: Vec<ChangedFile> = from
+                .iter()
+                .filter(|(path, _)| !other.contains_key(*path) && keep(path))
+                .map(|(path, &size)| ChangedFile {
+                    path: self.root.join(path).display().to_string(),
+                    size,
+                })
+                .collect();
+            files.sort_by_key(|f| std::cmp::Reverse(f.size));
+            files.truncate(top_n);
+            files
+        };
+
+        SnapshotDiff {
+            snapshot: old.id.clone(),
+            taken_at: old.taken_at.clone(),
+            disk_usage: old.disk_usage,
+            before: old.total,
+            after: self.total,
+            directories,
+            extensions,
+            new_large_files: files_missing_from(&self.large_files, &old.large_files, &|_| true),
+            // Small files are not recorded, so a large file missing from
+            // this snapshot may only have shrunk; this one is of the tree on
+            // disk now, so look
+            deleted_files: files_missing_from(&old.large_files, &self.large_files, &|path| {
+                self.root.join(path).symlink_metadata().is_err()
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(path: &str, size: u64) -> FileInfo {
+        FileInfo::test_file(Path::new("/data").join(path), size, None)
+    }
+
+    #[test]
+    fn test_diff_reports_growth_and_large_files() {
+        let root = Path::new("/data");
+        let old = Snapshot::take(
+            root,
+            &[
+                file("a/x.log", 100),
+                file("a/b/big.iso", 5_000_000),
+                file("c/keep.txt", 10),
+            ],
+            false,
+        );
+        let new = Snapshot::take(
+            root,
+            &[
+                file("a/x.log", 300),
+                file("a/b/huge.mkv", 9_000_000),
+                file("c/keep.txt", 10),
+            ],
+            false,
+        );
+
+        assert_eq!(old.dirs["."].files, 3);
+        assert_eq!(old.dirs["a/b"].size, 5_000_000);
+
+        let diff = new.diff_from(&old, 1, 10);
+        let names: Vec<&str> = diff.directories.iter().map(|g| g.name.as_str()).collect();
+        // "c" did not change and "a/b" is below the depth limit
+        assert_eq!(names, [".", "a"]);
+        assert_eq!(diff.directories[1].change, 4_000_200);
+
+        let ext = |name: &str| diff.extensions.iter().find(|g| g.name == name).unwrap();
+        assert_eq!(ext("iso").change, -5_000_000);
+        assert_eq!(ext("mkv").after.files, 1);
+        assert_eq!(ext("log").change, 200);
+        assert!(diff.extensions.iter().all(|g| g.name != "txt"));
+
+        assert_eq!(diff.new_large_files.len(), 1);
+        assert!(diff.new_large_files[0].path.ends_with("huge.mkv"));
+        assert_eq!(diff.deleted_files.len(), 1);
+        assert_eq!(diff.deleted_files[0].size, 5_000_000);
+    }
+
+    #[test]
+    fn test_shrunk_large_file_is_not_deleted() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::write(root.join("log.txt"), "truncated").unwrap();
+        let at = |name: &str, size: u64| FileInfo::test_file(root.join(name), size, None);
+
+        let old = Snapshot::take(
+            root,
+            &[at("log.txt", 3_000_000), at("gone.iso", 2_000_000)],
+            false,
+        );
+        let new = Snapshot::take(root, &[at("log.txt", 9)], false);
+
+        let diff = new.diff_from(&old, 1, 10);
+        assert_eq!(diff.deleted_files.len(), 1);
+        assert!(diff.deleted_files[0].path.ends_with("gone.iso"));
+    }
+}
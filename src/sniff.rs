@@ -0,0 +1,260 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+/// How many leading bytes are read to identify a file.
+const SNIFF_LEN: usize = 512;
+
+/// How many leading bytes `looks_binary` looks at.
+pub const BINARY_CHECK_LEN: usize = 8000;
+
+/// Heuristic binary check: a NUL byte in the first 8000 bytes (the same
+/// test git uses). Text encodings that fiq can read never contain NUL.
+pub fn looks_binary(data: &[u8]) -> bool {
+    data[..data.len().min(BINARY_CHECK_LEN)].contains(&0)
+}
+
+/// A file type identified from content.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FileType {
+    pub mime: &'static str,
+    /// Canonical extension for this type
+    pub ext: &'static str,
+    /// Container formats (zip, plain text, XML) that many more specific
+    /// formats are built on; a file's own extension is usually more precise.
+    pub generic: bool,
+}
+
+const fn specific(mime: &'static str, ext: &'static str) -> FileType {
+    FileType {
+        mime,
+        ext,
+        generic: false,
+    }
+}
+
+const fn generic(mime: &'static str, ext: &'static str) -> FileType {
+    FileType {
+        mime,
+        ext,
+        generic: true,
+    }
+}
+
+/// Fixed signatures at offset 0, checked in order.
+const MAGIC: &[(&[u8], FileType)] = &[
+    (b"\x89PNG\r\n\x1a\n", specific("image/png", "png")),
+    (b"\xff\xd8\xff", specific("image/jpeg", "jpg")),
+    (b"GIF87a", specific("image/gif", "gif")),
+    (b"GIF89a", specific("image/gif", "gif")),
+    (b"II*\x00", specific("image/tiff", "tif")),
+    (b"MM\x00*", specific("image/tiff", "tif")),
+    (b"%PDF-", specific("application/pdf", "pdf")),
+    (b"\x1f\x8b", specific("application/gzip", "gz")),
+    (b"BZh", specific("application/x-bzip2", "bz2")),
+    (b"\xfd7zXZ\x00", specific("application/x-xz", "xz")),
+    (b"\x28\xb5\x2f\xfd", specific("application/zstd", "zst")),
+    (
+        b"7z\xbc\xaf\x27\x1c",
+        specific("application/x-7z-compressed", "7z"),
+    ),
+    (b"Rar!\x1a\x07", specific("application/vnd.rar", "rar")),
+    (b"\x7fELF", specific("application/x-elf", "bin")),
+    (
+        b"\xcf\xfa\xed\xfe",
+        specific("application/x-mach-binary", "bin"),
+    ),
+    (
+        b"\xce\xfa\xed\xfe",
+        specific("application/x-mach-binary", "bin"),
+    ),
+    (
+        b"MZ",
+        specific("application/vnd.microsoft.portable-executable", "exe"),
+    ),
+    (b"\x00asm", specific("application/wasm", "wasm")),
+    (
+        b"SQLite format 3\x00",
+        specific("application/vnd.sqlite3", "sqlite"),
+    ),
+    (b"fLaC", specific("audio/flac", "flac")),
+    (b"OggS", specific("audio/ogg", "ogg")),
+    (b"ID3", specific("audio/mpeg", "mp3")),
+    (b"\xff\xfb", specific("audio/mpeg", "mp3")),
+    (b"wOFF", specific("font/woff", "woff")),
+    (b"wOF2", specific("font/woff2", "woff2")),
+    (b"OTTO", specific("font/otf", "otf")),
+    (b"\x00\x01\x00\x00\x00", specific("font/ttf", "ttf")),
+];
+
+const ZIP: FileType = generic("application/zip", "zip");
+const XML: FileType = generic("application/xml", "xml");
+const TEXT: FileType = generic("text/plain", "txt");
+
+/// Identify a file type from its leading bytes.
+pub fn detect(head: &[u8]) -> Option<FileType> {
+    if let Some((_, t)) = MAGIC.iter().find(|(magic, _)| head.starts_with(magic)) {
+        return Some(*t);
+    }
+    if head.starts_with(b"PK\x03\x04") || head.starts_with(b"PK\x05\x06") {
+        return Some(detect_zip(head));
+    }
+    if head.len() >= 12 && &head[4..8] == b"ftyp" {
+        return Some(detect_iso_media(&head[8..12]));
+    }
+    if head.len() >= 12 && head.starts_with(b"RIFF") {
+        match &head[8..12] {
+            b"WEBP" => return Some(specific("image/webp", "webp")),
+            b"WAVE" => return Some(specific("audio/wav", "wav")),
+            b"AVI " => return Some(specific("video/x-msvideo", "avi")),
+            _ => {}
+        }
+    }
+    if head.starts_with(b"\x1a\x45\xdf\xa3") {
+        return Some(if contains(head, b"webm") {
+            specific("video/webm", "webm")
+        } else {
+            specific("video/x-matroska", "mkv")
+        });
+    }
+    if head.is_empty() || looks_binary(head) {
+        return None;
+    }
+    detect_text(head)
+}
+
+/// Read the start of a file and identify it. Empty and unreadable files
+/// are not identified.
+pub fn sniff(path: &Path) -> Option<FileType> {
+    let mut head = Vec::with_capacity(SNIFF_LEN);
+    File::open(path)
+        .ok()?
+        .take(SNIFF_LEN as u64)
+        .read_to_end(&mut head)
+        .ok()?;
+    detect(&head)
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack.windows(needle.len()).any(|w| w == needle)
+}
+
+/// Office and ebook formats are zip archives; look at the first entries.
+fn detect_zip(head: &[u8]) -> FileType {
+    // OpenDocument and EPUB store an uncompressed "mimetype" entry first
+    if head.len() > 38 && &head[30..38] == b"mimetype" {
+        let content = &head[38..];
+        for (mime, ext) in [
+            ("application/vnd.oasis.opendocument.text", "odt"),
+            ("application/vnd.oasis.opendocument.spreadsheet", "ods"),
+            ("application/vnd.oasis.opendocument.presentation", "odp"),
+            ("application/epub+zip", "epub"),
+        ] {
+            if content.starts_with(mime.as_bytes()) {
+                return specific(mime, ext);
+            }
+        }
+    }
+    if contains(head, b"word/") {
+        specific(
+            "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+            "docx",
+        )
+    } else if contains(head, b"xl/") {
+        specific(
+            "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+            "xlsx",
+        )
+    } else if contains(head, b"ppt/") {
+        specific(
+            "application/vnd.openxmlformats-officedocument.presentationml.presentation",
+            "pptx",
+        )
+    } else {
+        ZIP
+    }
+}
+
+/// ISO base media files (MP4, MOV, HEIC, ...) by major brand.
+fn detect_iso_media(brand: &[u8]) -> FileType {
+    match brand {
+        b"qt  " => specific("video/quicktime", "mov"),
+        b"M4A " | b"M4B " => specific("audio/mp4", "m4a"),
+        b"heic" | b"heix" | b"mif1" | b"msf1" => specific("image/heic", "heic"),
+        b"avif" => specific("image/avif", "avif"),
+        _ => specific("video/mp4", "mp4"),
+    }
+}
+
+fn detect_text(head: &[u8]) -> Option<FileType> {
+    // The read may cut a multi-byte character short; that still counts
+    if let Err(e) = std::str::from_utf8(head)
+        && e.error_len().is_some()
+    {
+        return None;
+    }
+    let text = String::from_utf8_lossy(head);
+    let start = text.trim_start_matches('\u{feff}').trim_start();
+    let lower = start.to_ascii_lowercase();
+
+    if lower.starts_with("<!doctype html") || lower.starts_with("<html") {
+        return Some(specific("text/html", "html"));
+    }
+    if lower.starts_with("<svg") || (lower.starts_with("<?xml") && lower.contains("<svg")) {
+        return Some(specific("image/svg+xml", "svg"));
+    }
+    if lower.starts_with("<?xml") {
+        return Some(XML);
+    }
+    if start.starts_with("#!") {
+        return Some(specific("text/x-shellscript", "sh"));
+    }
+    Some(TEXT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn zip_entry(name: &[u8], data: &[u8]) -> Vec<u8> {
+        let mut out = b"PK\x03\x04".to_vec();
+        out.extend_from_slice(&[0; 22]);
+        out.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        out.extend_from_slice(&[0, 0]);
+        out.extend_from_slice(name);
+        out.extend_from_slice(data);
+        out
+    }
+
+    #[test]
+    fn test_magic_numbers() {
+        let mime = |bytes: &[u8]| detect(bytes).map(|t| t.mime);
+        assert_eq!(mime(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"), Some("image/png"));
+        assert_eq!(mime(b"\xff\xd8\xff\xe0\0\x10JFIF"), Some("image/jpeg"));
+        assert_eq!(mime(b"%PDF-1.7\n"), Some("application/pdf"));
+        assert_eq!(mime(b"\x7fELF\x02\x01\x01"), Some("application/x-elf"));
+        assert_eq!(mime(b"\x1f\x8b\x08\0"), Some("application/gzip"));
+        assert_eq!(mime(b"\0\0\0\x20ftypisom\0\0\x02\0"), Some("video/mp4"));
+        assert_eq!(mime(b"\0\0\0\x14ftypqt  \0\0\0\0"), Some("video/quicktime"));
+        assert_eq!(mime(b"RIFF\0\0\0\0WEBPVP8 "), Some("image/webp"));
+        assert_eq!(mime(b"#!/bin/sh\necho hi\n"), Some("text/x-shellscript"));
+        assert_eq!(mime(b"<!DOCTYPE html><html>"), Some("text/html"));
+        assert_eq!(mime(b"hello\n"), Some("text/plain"));
+        assert_eq!(mime(b"\x01\x02\0\x03"), None);
+        assert_eq!(mime(b""), None);
+    }
+
+    #[test]
+    fn test_zip_based_formats() {
+        let docx = zip_entry(b"[Content_Types].xml", b"..word/document.xml");
+        assert_eq!(detect(&docx).unwrap().ext, "docx");
+
+        let odt = zip_entry(b"mimetype", b"application/vnd.oasis.opendocument.text");
+        assert_eq!(detect(&odt).unwrap().ext, "odt");
+
+        let plain = zip_entry(b"notes.txt", b"hello");
+        let t = detect(&plain).unwrap();
+        assert_eq!(t.mime, "application/zip");
+        assert!(t.generic);
+    }
+}
@@ -0,0 +1,450 @@
+use std::path::{Component, Path, PathBuf};
+
+use crate::commands::organize::{
+    SIZE_BUCKETS, TYPE_CATEGORIES, categorize_by_size, categorize_by_type, is_year_folder,
+};
+use crate::scanner::FileInfo;
+
+/// Default number of hex digits for `{hash}`.
+const DEFAULT_HASH_LEN: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Field {
+    Name,
+    Stem,
+    Ext,
+    Category,
+    Year,
+    Month,
+    Day,
+    Date,
+    Size,
+    Parent,
+    Hash(usize),
+    /// Regex capture group of `fiq rename --match`
+    Capture(usize),
+    /// Position in the batch, zero-padded to the given width
+    Counter(usize),
+}
+
+impl Field {
+    /// Parse a placeholder name with its optional `:N` argument.
+    fn parse(name: &str, arg: Option<&str>) -> Option<Field> {
+        let number = |default: usize| arg.map_or(Some(default), |a| a.parse().ok());
+        let plain = |field| arg.is_none().then_some(field);
+        match name {
+            "name" => plain(Field::Name),
+            "stem" => plain(Field::Stem),
+            "ext" => plain(Field::Ext),
+            "category" => plain(Field::Category),
+            "year" => plain(Field::Year),
+            "month" => plain(Field::Month),
+            "day" => plain(Field::Day),
+            "date" => plain(Field::Date),
+            "size" => plain(Field::Size),
+            "parent" => plain(Field::Parent),
+            "hash" => number(DEFAULT_HASH_LEN)
+                .filter(|len| (1..=64).contains(len))
+                .map(Field::Hash),
+            "n" => number(1)
+                .filter(|width| (1..=20).contains(width))
+                .map(Field::Counter),
+            _ if !name.is_empty() && name.bytes().all(|b| b.is_ascii_digit()) => {
+                plain(Field::Capture(name.parse().ok()?))
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether this field depends on the individual file, as opposed to
+    /// the folder it is sorted into.
+    fn names_file(self) -> bool {
+        matches!(
+            self,
+            Field::Name
+                | Field::Stem
+                | Field::Ext
+                | Field::Hash(_)
+                | Field::Capture(_)
+                | Field::Counter(_)
+        )
+    }
+}
+
+/// Case conversion applied to a placeholder's value, e.g. `{stem:lower}`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Case {
+    Keep,
+    Upper,
+    Lower,
+    Title,
+}
+
+impl Case {
+    fn parse(name: &str) -> Option<Case> {
+        match name {
+            "upper" => Some(Case::Upper),
+            "lower" => Some(Case::Lower),
+            "title" => Some(Case::Title),
+            _ => None,
+        }
+    }
+
+    fn apply(self, value: String) -> String {
+        match self {
+            Case::Keep => value,
+            Case::Upper => value.to_uppercase(),
+            Case::Lower => value.to_lowercase(),
+            Case::Title => {
+                // Capitalize each run of letters and digits
+                let mut out = String::with_capacity(value.len());
+                let mut word_start = true;
+                for c in value.chars() {
+                    if !c.is_alphanumeric() {
+                        out.push(c);
+                        word_start = true;
+                    } else if word_start {
+                        out.extend(c.to_uppercase());
+                        word_start = false;
+                    } else {
+                        out.extend(c.to_lowercase());
+                    }
+                }
+                out
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Part {
+    Literal(String),
+    Field(Field, Case),
+    Separator,
+}
+
+/// Per-file values a template can use besides the file itself.
+#[derive(Default)]
+pub struct Values<'a> {
+    /// blake3 hex digest, for `{hash}`
+    pub hash: Option<&'a str>,
+    /// Captures of the `fiq rename --match` pattern on the file name
+    pub captures: Option<&'a regex::Captures<'a>>,
+    /// 1-based position of the file in the batch, for `{n}`
+    pub counter: usize,
+}
+
+/// A destination path template such as `{category}/{year}/{stem}.{ext}`.
+///
+/// Placeholders: `{name}`, `{stem}`, `{ext}`, `{category}` (as `--by type`),
+/// `{year}`, `{month}`, `{day}`, `{date}` (modification date), `{size}` (as
+/// `--by size`), `{parent}` (containing directory), `{hash}` / `{hash:N}`
+/// (first 8 or N hex digits of the blake3 hash), `{n}` / `{n:N}` (counter,
+/// zero-padded to N digits) and `{1}`, `{2}`, ... (regex capture groups).
+/// Any placeholder takes a `:upper`, `:lower` or `:title` suffix.
+///
+/// A `.` directly before an empty `{ext}` is dropped. When the last path
+/// component does not mention the file (`{name}`, `{stem}`, `{ext}` or
+/// `{hash}`), the original file name is appended, so `{category}/{year}`
+/// keeps names as they are.
+#[derive(Debug)]
+pub struct Template {
+    parts: Vec<Part>,
+    keeps_name: bool,
+}
+
+impl Template {
+    pub fn parse(text: &str) -> Result<Template, String> {
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut chars = text.chars();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => name.push(c),
+                            None => return Err(format!("Unclosed placeholder {{{}", name)),
+                        }
+                    }
+                    let (base, case) = match name.rsplit_once(':') {
+                        Some((base, case)) if let Some(case) = Case::parse(case) => (base, case),
+                        _ => (name.as_str(), Case::Keep),
+                    };
+                    let (base, arg) = match base.split_once(':') {
+                        Some((base, arg)) => (base, Some(arg)),
+                        None => (base, None),
+                    };
+                    let field = Field::parse(base, arg)
+                        .ok_or_else(|| format!("Unknown placeholder {{{}}}", name))?;
+                    if !literal.is_empty() {
+                        parts.push(Part::Literal(std::mem::take(&mut literal)));
+                    }
+                    parts.push(Part::Field(field, case));
+                }
+                '}' => return Err("Unmatched } in template".to_string()),
+                '/' | '\\' => {
+                    if !literal.is_empty() {
+                        parts.push(Part::Literal(std::mem::take(&mut literal)));
+                    }
+                    parts.push(Part::Separator);
+                }
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            parts.push(Part::Literal(literal));
+        }
+
+        if text.starts_with('/') || text.starts_with('\\') {
+            return Err("Template must be a relative path".to_string());
+        }
+        if text.split(['/', '\\']).any(|c| c == "..") {
+            return Err("Template may not contain \"..\"".to_string());
+        }
+
+        let last = parts
+            .iter()
+            .rposition(|p| matches!(p, Part::Separator))
+            .map_or(0, |i| i + 1);
+        let keeps_name = !parts[last..]
+            .iter()
+            .any(|p| matches!(p, Part::Field(f, _) if f.names_file()));
+
+        Ok(Template { parts, keeps_name })
+    }
+
+    /// Whether `name` could be the first folder of a rendered path. Only
+    /// answers for a lone literal, `{category}`, `{year}` or `{size}`.
+    pub fn is_top_folder(&self, name: &str) -> bool {
+        let Some(end) = self.parts.iter().position(|p| matches!(p, Part::Separator)) else {
+            return false;
+        };
+        match &self.parts[..end] {
+            [Part::Literal(s)] => s == name,
+            [Part::Field(Field::Category, Case::Keep)] => TYPE_CATEGORIES.contains(&name),
+            [Part::Field(Field::Year, _)] => is_year_folder(name),
+            [Part::Field(Field::Size, Case::Keep)] => SIZE_BUCKETS.contains(&name),
+            _ => false,
+        }
+    }
+
+    /// Whether rendering needs the file's content hash.
+    pub fn needs_hash(&self) -> bool {
+        self.parts
+            .iter()
+            .any(|p| matches!(p, Part::Field(Field::Hash(_), _)))
+    }
+
+    /// Highest capture group the template refers to, if any.
+    pub fn max_capture(&self) -> Option<usize> {
+        self.parts
+            .iter()
+            .filter_map(|p| match p {
+                Part::Field(Field::Capture(i), _) => Some(*i),
+                _ => None,
+            })
+            .max()
+    }
+
+    /// Destination path for a file, relative to the output directory.
+    pub fn render(&self, file: &FileInfo, values: &Values<'_>) -> Result<PathBuf, String> {
+        let out = self.expand(file, values)?;
+        let name = file.path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+
+        let mut path = PathBuf::from(out.trim_end_matches('/'));
+        if self.keeps_name || out.ends_with('/') {
+            path.push(name);
+        }
+
+        let valid = path.components().all(|c| match c {
+            Component::Normal(s) => s != "." && !s.is_empty(),
+            _ => false,
+        });
+        if !valid || path == Path::new("") {
+            return Err(format!(
+                "template produced invalid path \"{}\"",
+                path.display()
+            ));
+        }
+        Ok(path)
+    }
+
+    /// New file name for `fiq rename`. Unlike `render`, the template must
+    /// produce a single name and the original name is never appended.
+    pub fn render_name(&self, file: &FileInfo, values: &Values<'_>) -> Result<String, String> {
+        let out = self.expand(file, values)?;
+        if out.is_empty() || out == "." || out == ".." || out.contains('/') {
+            return Err(format!("template produced invalid file name \"{}\"", out));
+        }
+        Ok(out)
+    }
+
+    /// Substitute every placeholder; separators become `/`.
+    fn expand(&self, file: &FileInfo, values: &Values<'_>) -> Result<String, String> {
+        let name = file.path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+        let stem = file.path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+        let ext = file.extension.as_deref().unwrap_or("");
+        let date = file.modified.map(chrono::DateTime::<chrono::Local>::from);
+        let date_part = |fmt: &str| {
+            date.map(|d| d.format(fmt).to_string())
+                .unwrap_or_else(|| "Unknown".to_string())
+        };
+
+        let mut out = String::new();
+        for part in &self.parts {
+            let (field, case) = match part {
+                Part::Literal(s) => {
+                    out.push_str(s);
+                    continue;
+                }
+                Part::Separator => {
+                    out.push('/');
+                    continue;
+                }
+                Part::Field(field, case) => (*field, *case),
+            };
+            let value = match field {
+                Field::Name => name.to_string(),
+                Field::Stem => stem.to_string(),
+                Field::Ext => {
+                    if ext.is_empty() && out.ends_with('.') {
+                        out.pop();
+                    }
+                    ext.to_string()
+                }
+                Field::Category => categorize_by_type(ext).to_string(),
+                Field::Year => date_part("%Y"),
+                Field::Month => date_part("%m"),
+                Field::Day => date_part("%d"),
+                Field::Date => date_part("%Y-%m-%d"),
+                Field::Size => categorize_by_size(file.size).to_string(),
+                Field::Parent => file
+                    .path
+                    .parent()
+                    .and_then(|p| p.file_name())
+                    .and_then(|n| n.to_str())
+                    .unwrap_or("")
+                    .to_string(),
+                Field::Hash(len) => {
+                    let hash = values.hash.ok_or("file could not be hashed")?;
+                    hash[..len.min(hash.len())].to_string()
+                }
+                Field::Capture(i) => {
+                    let captures = values
+                        .captures
+                        .ok_or_else(|| format!("{{{}}} needs a --match pattern", i))?;
+                    captures.get(i).map_or("", |m| m.as_str()).to_string()
+                }
+                Field::Counter(width) => format!("{:0width$}", values.counter, width = width),
+            };
+            // Values come from file names; never let them add path levels
+            out.push_str(&case.apply(value).replace(['/', '\\'], "_"));
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, SystemTime};
+
+    fn file(path: &str, size: u64) -> FileInfo {
+        // 2024-05-15 12:00 UTC: same calendar day in every time zone
+        let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1_715_774_400);
+        FileInfo::test_file(path, size, Some(modified))
+    }
+
+    fn render(template: &str, f: &FileInfo) -> String {
+        Template::parse(template)
+            .unwrap()
+            .render(
+                f,
+                &Values {
+                    hash: Some("0123456789abcdef"),
+                    counter: 7,
+                    ..Values::default()
+                },
+            )
+            .unwrap()
+            .display()
+            .to_string()
+    }
+
+    #[test]
+    fn test_placeholders() {
+        let f = file("/home/me/Downloads/IMG_1.jpg", 5_000);
+        assert_eq!(
+            render("{category}/{year}/{month}/{stem}.{ext}", &f),
+            "Images/2024/05/IMG_1.jpg"
+        );
+        assert_eq!(
+            render("{parent}/{day}-{hash}.{ext}", &f),
+            "Downloads/15-01234567.jpg"
+        );
+        assert_eq!(
+            render("{size}/{hash:4}_{name}", &f),
+            "Small (1KB-1MB)/0123_IMG_1.jpg"
+        );
+    }
+
+    #[test]
+    fn test_keeps_name_and_drops_empty_ext() {
+        let f = file("/tmp/Makefile", 10);
+        assert_eq!(render("{category}/{year}", &f), "Other/2024/Makefile");
+        assert_eq!(render("{category}/", &f), "Other/Makefile");
+        assert_eq!(render("{year}/{stem}.{ext}", &f), "2024/Makefile");
+    }
+
+    #[test]
+    fn test_captures_counter_and_case() {
+        let f = file("/tmp/IMG_0042 holiday.JPG", 10);
+        let re = regex::Regex::new(r"^IMG_(\d+) (\w+)").unwrap();
+        let captures = re.captures("IMG_0042 holiday.JPG").unwrap();
+        let values = Values {
+            captures: Some(&captures),
+            counter: 7,
+            ..Values::default()
+        };
+        let name = |template: &str| {
+            Template::parse(template)
+                .unwrap()
+                .render_name(&f, &values)
+                .unwrap()
+        };
+        assert_eq!(name("{2:title}_{1}.{ext}"), "Holiday_0042.jpg");
+        assert_eq!(name("{date}-{n:3}.{ext:upper}"), "2024-05-15-007.JPG");
+        assert_eq!(name("{stem:lower}"), "img_0042 holiday");
+        assert!(
+            Template::parse("{1}/{2}")
+                .unwrap()
+                .render_name(&f, &values)
+                .is_err()
+        );
+        assert_eq!(
+            render("{category}/{n}-{name}", &f),
+            "Images/7-IMG_0042 holiday.JPG"
+        );
+    }
+
+    #[test]
+    fn test_invalid_templates() {
+        assert!(Template::parse("{nope}/x").is_err());
+        assert!(Template::parse("{year").is_err());
+        assert!(Template::parse("../{name}").is_err());
+        assert!(Template::parse("/abs/{name}").is_err());
+        assert!(Template::parse("{hash:0}").is_err());
+        assert!(Template::parse("{stem:shout}").is_err());
+        assert!(Template::parse("{n:x}").is_err());
+        assert_eq!(
+            Template::parse("{2}-{1:upper}").unwrap().max_capture(),
+            Some(2)
+        );
+        assert!(!Template::parse("{category}").unwrap().needs_hash());
+        assert!(Template::parse("{hash:12}").unwrap().needs_hash());
+    }
+}
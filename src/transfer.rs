@@ -0,0 +1,235 @@
+use std::fs::{self, File, FileTimes, Metadata, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+/// Chunk size for copying file contents.
+const COPY_BUF: usize = 1 << 20;
+
+/// Move a file, symlink or directory. Within a device this is a plain
+/// rename. Across devices the data is copied to a temporary name next to
+/// the destination, synced, checked against the source with blake3 and
+/// renamed into place; only then is the source removed. Permissions,
+/// timestamps, ownership (where allowed) and extended attributes are kept.
+pub fn move_path(from: &Path, to: &Path) -> io::Result<()> {
+    match fs::rename(from, to) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == io::ErrorKind::CrossesDevices || e.raw_os_error() == Some(18) => {
+            let meta = fs::symlink_metadata(from)?;
+            let staging = staging_path(to);
+            let copied = if meta.is_dir() {
+                copy_dir(from, &staging, &meta)
+            } else if meta.file_type().is_symlink() {
+                copy_symlink(from, &staging)
+            } else {
+                copy_file(from, &staging, &meta)
+            };
+            if let Err(e) = copied.and_then(|()| fs::rename(&staging, to)) {
+                let _ = if meta.is_dir() {
+                    fs::remove_dir_all(&staging)
+                } else {
+                    fs::remove_file(&staging)
+                };
+                return Err(e);
+            }
+            sync_parent(to)?;
+
+            if meta.is_dir() {
+                fs::remove_dir_all(from)
+            } else {
+                fs::remove_file(from)
+            }
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Create `dir` and any missing parents. Returns the directories that
+/// did not exist before, outermost first, so a run can journal them.
+pub fn create_dirs(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let missing: Vec<PathBuf> = dir
+        .ancestors()
+        .take_while(|d| !d.as_os_str().is_empty() && d.symlink_metadata().is_err())
+        .map(Path::to_path_buf)
+        .collect();
+    fs::create_dir_all(dir)?;
+    Ok(missing.into_iter().rev().collect())
+}
+
+/// Temporary name in the destination's directory, so the final rename
+/// stays on one device and is atomic.
+fn staging_path(to: &Path) -> PathBuf {
+    let name = to.file_name().and_then(|n| n.to_str()).unwrap_or("file");
+    to.with_file_name(format!(".{}.fiq-tmp-{}", name, std::process::id()))
+}
+
+/// Copy a regular file's contents and metadata to a new file at `to`,
+/// then read the copy back and compare blake3 hashes.
+fn copy_file(from: &Path, to: &Path, meta: &Metadata) -> io::Result<()> {
+    let mut source = File::open(from)?;
+    let mut target = OpenOptions::new().write(true).create_new(true).open(to)?;
+
+    let mut hasher = blake3::Hasher::new();
+    let mut buf = vec![0u8; COPY_BUF];
+    loop {
+        let n = source.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+        target.write_all(&buf[..n])?;
+    }
+    target.sync_all()?;
+
+    let mut check = blake3::Hasher::new();
+    check.update_reader(File::open(to)?)?;
+    if check.finalize() != hasher.finalize() {
+        return Err(io::Error::other(format!(
+            "copy of {} does not match the original",
+            from.display()
+        )));
+    }
+
+    copy_metadata(&source, &target, meta)?;
+    target.sync_all()
+}
+
+/// Recreate a symlink pointing at the same target.
+fn copy_symlink(from: &Path, to: &Path) -> io::Result<()> {
+    let target = fs::read_link(from)?;
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::symlink(target, to)
+    }
+    #[cfg(windows)]
+    {
+        if fs::metadata(from).is_ok_and(|m| m.is_dir()) {
+            std::os::windows::fs::symlink_dir(target, to)
+        } else {
+            std::os::windows::fs::symlink_file(target, to)
+        }
+    }
+}
+
+/// Copy a directory tree. Directory metadata is applied after the
+/// contents, so a read-only directory can still be filled first.
+fn copy_dir(from: &Path, to: &Path, meta: &Metadata) -> io::Result<()> {
+    fs::create_dir(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        // Not followed: symlinks inside the tree are copied as links
+        let entry_meta = entry.metadata()?;
+        let target = to.join(entry.file_name());
+        if entry_meta.is_dir() {
+            copy_dir(&entry.path(), &target, &entry_meta)?;
+        } else if entry_meta.file_type().is_symlink() {
+            copy_symlink(&entry.path(), &target)?;
+        } else {
+            copy_file(&entry.path(), &target, &entry_meta)?;
+        }
+    }
+
+    #[cfg(unix)]
+    {
+        let dir = File::open(to)?;
+        copy_metadata(&File::open(from)?, &dir, meta)?;
+        dir.sync_all()?;
+    }
+    #[cfg(not(unix))]
+    fs::set_permissions(to, meta.permissions())?;
+    Ok(())
+}
+
+/// Give `target` the ownership, extended attributes, permissions and
+/// timestamps of `source`. Ownership and attributes are best effort:
+/// only root may give files away, and not every file system stores every
+/// attribute namespace.
+fn copy_metadata(source: &File, target: &File, meta: &Metadata) -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        use xattr::FileExt;
+
+        let _ = std::os::unix::fs::fchown(target, Some(meta.uid()), Some(meta.gid()));
+        if let Ok(names) = source.list_xattr() {
+            for name in names {
+                if let Ok(Some(value)) = source.get_xattr(&name) {
+                    let _ = target.set_xattr(&name, &value);
+                }
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = source;
+
+    // After chown, which may clear setuid bits
+    target.set_permissions(meta.permissions())?;
+    let mut times = FileTimes::new().set_modified(meta.modified()?);
+    if let Ok(accessed) = meta.accessed() {
+        times = times.set_accessed(accessed);
+    }
+    target.set_times(times)
+}
+
+/// Make a rename durable by syncing the directory that holds it.
+fn sync_parent(path: &Path) -> io::Result<()> {
+    #[cfg(unix)]
+    if let Some(parent) = path.parent() {
+        let parent = if parent.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            parent
+        };
+        File::open(parent)?.sync_all()?;
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, SystemTime};
+
+    #[test]
+    fn test_copy_file_keeps_metadata() {
+        let dir = tempfile::tempdir().unwrap();
+        let from = dir.path().join("a.txt");
+        fs::write(&from, "hello").unwrap();
+        let mtime = SystemTime::UNIX_EPOCH + Duration::from_secs(1_600_000_000);
+        File::options()
+            .write(true)
+            .open(&from)
+            .unwrap()
+            .set_modified(mtime)
+            .unwrap();
+        let mut perms = fs::metadata(&from).unwrap().permissions();
+        perms.set_readonly(true);
+        fs::set_permissions(&from, perms).unwrap();
+
+        let to = dir.path().join("b.txt");
+        copy_file(&from, &to, &fs::metadata(&from).unwrap()).unwrap();
+        let meta = fs::metadata(&to).unwrap();
+        assert_eq!(fs::read_to_string(&to).unwrap(), "hello");
+        assert_eq!(meta.modified().unwrap(), mtime);
+        assert!(meta.permissions().readonly());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_copy_dir_tree() {
+        let dir = tempfile::tempdir().unwrap();
+        let from = dir.path().join("src");
+        fs::create_dir_all(from.join("sub")).unwrap();
+        fs::write(from.join("sub/a.txt"), "a").unwrap();
+        std::os::unix::fs::symlink("sub/a.txt", from.join("link")).unwrap();
+
+        let to = dir.path().join("dst");
+        copy_dir(&from, &to, &fs::metadata(&from).unwrap()).unwrap();
+        assert_eq!(fs::read_to_string(to.join("sub/a.txt")).unwrap(), "a");
+        assert_eq!(
+            fs::read_link(to.join("link")).unwrap(),
+            Path::new("sub/a.txt")
+        );
+    }
+}
@@ -124,6 +124,227 @@ fn test_duplicates_min_size() {
     assert!(stdout.contains("0 B"));
 }
 
+#[test]
+fn test_checksum_create_and_verify() {
+    let dir = create_test_dir();
+    let manifest = dir.path().join("manifest.b3");
+
+    let output = Command::new(fiq_bin())
+        .args([
+            "checksum",
+            "create",
+            dir.path().to_str().unwrap(),
+            "-o",
+            manifest.to_str().unwrap(),
+        ])
+        .output()
+        .expect("failed to run fiq checksum create");
+    assert!(output.status.success());
+    let text = fs::read_to_string(&manifest).unwrap();
+    assert!(text.lines().any(|l| l.ends_with("  hello.txt")));
+    assert!(!text.contains("manifest.b3"));
+
+    let output = Command::new(fiq_bin())
+        .args(["checksum", "verify", manifest.to_str().unwrap()])
+        .output()
+        .expect("failed to run fiq checksum verify");
+    assert!(output.status.success());
+
+    fs::write(dir.path().join("hello.txt"), "Hello, bit rot!").unwrap();
+    fs::remove_file(dir.path().join("readme.md")).unwrap();
+    fs::write(dir.path().join("added.txt"), "new file").unwrap();
+
+    let output = Command::new(fiq_bin())
+        .args(["checksum", "verify", manifest.to_str().unwrap()])
+        .output()
+        .expect("failed to run fiq checksum verify");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!output.status.success());
+    assert!(stdout.contains("hello.txt"));
+    assert!(stdout.contains("readme.md"));
+    assert!(stdout.contains("added.txt"));
+}
+
+#[test]
+fn test_checksum_verify_dot_slash_manifest() {
+    let dir = create_test_dir();
+    let manifest = dir.path().join("SUMS.b3");
+
+    let output = Command::new(fiq_bin())
+        .args(["checksum", "create", dir.path().to_str().unwrap()])
+        .output()
+        .expect("failed to run fiq checksum create");
+    assert!(output.status.success());
+    // As written by `b3sum ./*`, with one absolute path mixed in
+    let text: String = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| {
+            let (hash, rel) = line.split_once("  ").unwrap();
+            if rel == "hello.txt" {
+                format!("{}  {}\n", hash, dir.path().join(rel).display())
+            } else {
+                format!("{}  ./{}\n", hash, rel)
+            }
+        })
+        .collect();
+    fs::write(&manifest, text).unwrap();
+
+    let output = Command::new(fiq_bin())
+        .args(["checksum", "verify", manifest.to_str().unwrap()])
+        .output()
+        .expect("failed to run fiq checksum verify");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    // Only new files are listed by name
+    assert!(!stdout.contains("main.rs"));
+    assert!(!stdout.contains("hello.txt"));
+    assert!(!stdout.contains("nested.txt"));
+}
+
+#[test]
+fn test_diff_json() {
+    let left = tempfile::tempdir().unwrap();
+    let right = tempfile::tempdir().unwrap();
+
+    fs::write(left.path().join("same.txt"), "unchanged").unwrap();
+    fs::write(right.path().join("same.txt"), "unchanged").unwrap();
+    fs::write(left.path().join("edited.txt"), "version one").unwrap();
+    fs::write(right.path().join("edited.txt"), "version two!").unwrap();
+    fs::write(left.path().join("gone.txt"), "only on the left").unwrap();
+    fs::write(right.path().join("new.txt"), "only on the right").unwrap();
+    fs::write(left.path().join("old_name.bin"), "moved content").unwrap();
+    fs::create_dir(right.path().join("archive")).unwrap();
+    fs::write(right.path().join("archive/new_name.bin"), "moved content").unwrap();
+
+    let output = Command::new(fiq_bin())
+        .args([
+            "diff",
+            left.path().to_str().unwrap(),
+            right.path().to_str().unwrap(),
+            "--verify",
+            "--json",
+        ])
+        .output()
+        .expect("failed to run fiq diff");
+
+    assert!(output.status.success());
+    let result: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("invalid diff JSON");
+    assert_eq!(result["same"], 1);
+    assert_eq!(result["modified"][0]["path"], "edited.txt");
+    assert_eq!(result["only_left"][0]["path"], "gone.txt");
+    assert_eq!(result["only_right"][0]["path"], "new.txt");
+    assert_eq!(result["moved"][0]["from"], "old_name.bin");
+    assert!(
+        result["moved"][0]["to"]
+            .as_str()
+            .unwrap()
+            .ends_with("new_name.bin")
+    );
+
+    let output = Command::new(fiq_bin())
+        .args([
+            "diff",
+            left.path().to_str().unwrap(),
+            right.path().join("missing").to_str().unwrap(),
+        ])
+        .output()
+        .expect("failed to run fiq diff");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Not a directory"));
+}
+
+#[test]
+fn test_find_copies() {
+    let dir = create_test_dir();
+    let elsewhere = tempfile::tempdir().unwrap();
+    fs::write(elsewhere.path().join("copy3.txt"), "duplicate content here").unwrap();
+
+    let output = Command::new(fiq_bin())
+        .args([
+            "find-copies",
+            dir.path().join("copy1.txt").to_str().unwrap(),
+            dir.path().to_str().unwrap(),
+            elsewhere.path().to_str().unwrap(),
+            "--no-cache",
+        ])
+        .output()
+        .expect("failed to run fiq find-copies");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert!(stdout.contains("copy2.txt"));
+    assert!(stdout.contains("copy3.txt"));
+    // The query file itself is not a copy
+    assert!(!stdout.contains("copy1.txt"));
+}
+
+#[test]
+fn test_find_copies_rejects_bad_target() {
+    let dir = create_test_dir();
+
+    let output = Command::new(fiq_bin())
+        .args(["find-copies", "not-a-hash", dir.path().to_str().unwrap()])
+        .output()
+        .expect("failed to run fiq find-copies");
+
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_similar_images() {
+    let dir = create_test_dir();
+
+    let output = Command::new(fiq_bin())
+        .args(["similar", dir.path().to_str().unwrap(), "--images"])
+        .output()
+        .expect("failed to run fiq similar --images");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert!(stdout.contains("Similar Files"));
+}
+
+#[test]
+fn test_similar_text() {
+    let dir = create_test_dir();
+
+    let output = Command::new(fiq_bin())
+        .args(["similar", dir.path().to_str().unwrap(), "--text"])
+        .output()
+        .expect("failed to run fiq similar --text");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    // copy1.txt and copy2.txt are identical, so they must cluster
+    assert!(stdout.contains("copy1.txt") && stdout.contains("copy2.txt"));
+}
+
+#[test]
+fn test_similar_requires_mode() {
+    let dir = create_test_dir();
+
+    let output = Command::new(fiq_bin())
+        .args(["similar", dir.path().to_str().unwrap()])
+        .output()
+        .expect("failed to run fiq similar");
+
+    assert!(!output.status.success());
+
+    let output = Command::new(fiq_bin())
+        .args([
+            "similar",
+            dir.path().to_str().unwrap(),
+            "--images",
+            "--algorithm",
+            "ahash",
+        ])
+        .output()
+        .expect("failed to run fiq similar");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("invalid value"));
+}
+
 #[test]
 fn test_search_by_name() {
     let dir = create_test_dir();
@@ -237,6 +458,895 @@ fn test_organize_by_size() {
     assert!(stdout.contains("Tiny") || stdout.contains("Small"));
 }
 
+#[test]
+fn test_organize_then_undo() {
+    let dir = create_test_dir();
+    let state = tempfile::tempdir().unwrap();
+
+    let output = Command::new(fiq_bin())
+        .args(["organize", dir.path().to_str().unwrap(), "--by", "type"])
+        .env("FIQ_STATE_DIR", state.path())
+        .output()
+        .expect("failed to run fiq organize");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert!(stdout.contains("fiq undo"));
+    assert!(dir.path().join("Code/main.rs").exists());
+    assert!(!dir.path().join("main.rs").exists());
+
+    // An edited file must not be moved back without --force
+    fs::write(
+        dir.path().join("Documents/hello.txt"),
+        "edited after organize",
+    )
+    .unwrap();
+
+    let output = Command::new(fiq_bin())
+        .arg("undo")
+        .env("FIQ_STATE_DIR", state.path())
+        .output()
+        .expect("failed to run fiq undo");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!output.status.success());
+    assert!(stdout.contains("hello.txt"));
+    assert!(dir.path().join("main.rs").exists());
+    assert!(dir.path().join("subdir/nested.txt").exists());
+    assert!(!dir.path().join("hello.txt").exists());
+
+    // The skipped entry stays in the journal for a forced retry
+    let output = Command::new(fiq_bin())
+        .args(["undo", "--force"])
+        .env("FIQ_STATE_DIR", state.path())
+        .output()
+        .expect("failed to run fiq undo --force");
+    assert!(output.status.success());
+    assert_eq!(
+        fs::read_to_string(dir.path().join("hello.txt")).unwrap(),
+        "edited after organize"
+    );
+    // The category folders organize created are gone again
+    assert!(!dir.path().join("Code").exists());
+    assert!(!dir.path().join("Documents").exists());
+
+    let output = Command::new(fiq_bin())
+        .args(["undo", "--list"])
+        .env("FIQ_STATE_DIR", state.path())
+        .output()
+        .expect("failed to run fiq undo --list");
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Nothing to undo"));
+}
+
+#[test]
+fn test_undo_rejects_run_id_outside_journal() {
+    let state = tempfile::tempdir().unwrap();
+    let outside = tempfile::tempdir().unwrap();
+    let moved = outside.path().join("moved.txt");
+    fs::write(&moved, "data").unwrap();
+    // A journal-shaped file that would move `moved.txt` if it were replayed
+    let journal = outside.path().join("data.jsonl");
+    fs::write(
+        &journal,
+        format!(
+            "{{\"timestamp\":\"\",\"action\":\"move\",\"from\":\"{}\",\"to\":\"{}\",\"size\":4,\"modified\":null}}\n",
+            outside.path().join("restored.txt").display(),
+            moved.display()
+        ),
+    )
+    .unwrap();
+    fs::create_dir_all(state.path().join("journal")).unwrap();
+    // Both temp dirs share a parent: journal/../../<outside>/data
+    let run_id = format!(
+        "../../{}/data",
+        outside.path().file_name().unwrap().to_str().unwrap()
+    );
+
+    let output = Command::new(fiq_bin())
+        .args(["undo", &run_id])
+        .env("FIQ_STATE_DIR", state.path())
+        .output()
+        .expect("failed to run fiq undo");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Invalid run id"));
+    assert!(moved.exists());
+    assert!(journal.exists());
+}
+
+#[test]
+fn test_organize_with_rules_file() {
+    let dir = create_test_dir();
+    let config = tempfile::tempdir().unwrap();
+    let state = tempfile::tempdir().unwrap();
+    let rules = config.path().join("rules.toml");
+    fs::write(
+        &rules,
+        r#"
+fallback = "Unsorted"
+
+[[rule]]
+dest = "Copies"
+name_regex = "^copy\\d"
+
+[[rule]]
+dest = "Source/Rust"
+extensions = ["rs"]
+"#,
+    )
+    .unwrap();
+
+    let output = Command::new(fiq_bin())
+        .args([
+            "organize",
+            dir.path().to_str().unwrap(),
+            "--rules",
+            rules.to_str().unwrap(),
+        ])
+        .env("FIQ_STATE_DIR", state.path())
+        .output()
+        .expect("failed to run fiq organize --rules");
+    assert!(output.status.success());
+    assert!(dir.path().join("Copies/copy1.txt").exists());
+    assert!(dir.path().join("Source/Rust/main.rs").exists());
+    assert!(dir.path().join("Unsorted/data.json").exists());
+}
+
+#[test]
+fn test_organize_with_template() {
+    let dir = create_test_dir();
+
+    let output = Command::new(fiq_bin())
+        .args([
+            "organize",
+            dir.path().to_str().unwrap(),
+            "--template",
+            "{category}/{parent}/{stem}-{hash:6}.{ext}",
+            "--dry-run",
+        ])
+        .output()
+        .expect("failed to run fiq organize --template");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert!(stdout.contains("subdir"));
+    assert!(stdout.contains("main-"));
+
+    let output = Command::new(fiq_bin())
+        .args([
+            "organize",
+            dir.path().to_str().unwrap(),
+            "--template",
+            "{bogus}/{name}",
+        ])
+        .output()
+        .expect("failed to run fiq organize --template");
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Unknown placeholder"));
+    assert!(dir.path().join("main.rs").exists());
+
+    // {n} numbers files in path order
+    let numbered = tempfile::tempdir().unwrap();
+    let state = tempfile::tempdir().unwrap();
+    for name in ["c.txt", "a.txt", "d.txt", "b.txt"] {
+        fs::write(numbered.path().join(name), name).unwrap();
+    }
+    let output = Command::new(fiq_bin())
+        .args([
+            "organize",
+            numbered.path().to_str().unwrap(),
+            "--template",
+            "Numbered/{n:2}-{name}",
+        ])
+        .env("FIQ_STATE_DIR", state.path())
+        .output()
+        .expect("failed to run fiq organize --template");
+    assert!(output.status.success());
+    for name in ["01-a.txt", "02-b.txt", "03-c.txt", "04-d.txt"] {
+        assert!(
+            numbered.path().join("Numbered").join(name).exists(),
+            "{}",
+            name
+        );
+    }
+}
+
+#[test]
+fn test_sniffed_types() {
+    let dir = create_test_dir();
+    // A PNG without an extension and a PDF pretending to be text
+    fs::write(
+        dir.path().join("download"),
+        b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR",
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("notes.txt"),
+        b"%PDF-1.4\n%\xe2\xe3\xcf\xd3\n",
+    )
+    .unwrap();
+
+    let output = Command::new(fiq_bin())
+        .args(["stats", dir.path().to_str().unwrap(), "--by-type"])
+        .output()
+        .expect("failed to run fiq stats --by-type");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert!(stdout.contains("image/png"));
+    assert!(stdout.contains("application/pdf"));
+    assert!(stdout.contains("text/plain"));
+
+    let output = Command::new(fiq_bin())
+        .args([
+            "organize",
+            dir.path().to_str().unwrap(),
+            "--by",
+            "mime",
+            "--dry-run",
+        ])
+        .output()
+        .expect("failed to run fiq organize --by mime");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert!(stdout.contains(&format!("Images{}download", std::path::MAIN_SEPARATOR)));
+    // Generic text doesn't override a more specific extension
+    assert!(stdout.contains(&format!("Code{}main.rs", std::path::MAIN_SEPARATOR)));
+}
+
+#[test]
+fn test_organize_by_media_date() {
+    let dir = tempfile::tempdir().unwrap();
+
+    // Minimal MP4: ftyp + moov/mvhd created 2015-06-15 12:00 UTC
+    let mp4_box = |kind: &[u8], payload: &[u8]| {
+        let mut b = ((payload.len() + 8) as u32).to_be_bytes().to_vec();
+        b.extend_from_slice(kind);
+        b.extend_from_slice(payload);
+        b
+    };
+    let created = 1_434_369_600u32 + 2_082_844_800;
+    let mut mvhd = vec![0u8; 4];
+    mvhd.extend_from_slice(&created.to_be_bytes());
+    mvhd.extend_from_slice(&[0; 92]);
+    let mut video = mp4_box(b"ftyp", b"isom\0\0\0\0");
+    video.extend(mp4_box(b"moov", &mp4_box(b"mvhd", &mvhd)));
+    fs::write(dir.path().join("clip.mp4"), video).unwrap();
+
+    let sep = std::path::MAIN_SEPARATOR;
+    let run = |extra: &[&str]| {
+        let output = Command::new(fiq_bin())
+            .args([
+                "organize",
+                dir.path().to_str().unwrap(),
+                "--by",
+                "date",
+                "--dry-run",
+            ])
+            .args(extra)
+            .output()
+            .expect("failed to run fiq organize --by date");
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stdout).to_string()
+    };
+
+    assert!(run(&["--media-date"]).contains(&format!("2015{}06{}clip.mp4", sep, sep)));
+    assert!(!run(&[]).contains("2015"));
+}
+
+#[test]
+fn test_organize_copy_and_link_actions() {
+    let dir = create_test_dir();
+    let view = tempfile::tempdir().unwrap();
+    let state = tempfile::tempdir().unwrap();
+
+    for action in ["copy", "hardlink"] {
+        let out = view.path().join(action);
+        let output = Command::new(fiq_bin())
+            .args([
+                "organize",
+                dir.path().to_str().unwrap(),
+                "--action",
+                action,
+                "--output",
+                out.to_str().unwrap(),
+            ])
+            .env("FIQ_STATE_DIR", state.path())
+            .output()
+            .expect("failed to run fiq organize --action");
+        assert!(output.status.success());
+        assert!(out.join("Code/main.rs").exists());
+        // The source is left untouched
+        assert!(dir.path().join("main.rs").exists());
+    }
+
+    // Undo deletes what the latest run created and leaves the originals
+    let output = Command::new(fiq_bin())
+        .arg("undo")
+        .env("FIQ_STATE_DIR", state.path())
+        .output()
+        .expect("failed to run fiq undo");
+    assert!(output.status.success());
+    assert!(!view.path().join("hardlink/Code/main.rs").exists());
+    assert!(view.path().join("copy/Code/main.rs").exists());
+    assert!(dir.path().join("main.rs").exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_organize_symlink_action() {
+    let dir = create_test_dir();
+    let view = tempfile::tempdir().unwrap();
+
+    let output = Command::new(fiq_bin())
+        .args([
+            "organize",
+            dir.path().to_str().unwrap(),
+            "--action",
+            "symlink",
+            "--output",
+            view.path().to_str().unwrap(),
+        ])
+        .env("FIQ_STATE_DIR", view.path().join(".state"))
+        .output()
+        .expect("failed to run fiq organize --action symlink");
+    assert!(output.status.success());
+
+    let link = view.path().join("Documents/hello.txt");
+    assert!(link.symlink_metadata().unwrap().file_type().is_symlink());
+    assert_eq!(fs::read_to_string(&link).unwrap(), "Hello, world!");
+}
+
+#[test]
+fn test_organize_dedupe_mode() {
+    let dir = create_test_dir();
+    let state = tempfile::tempdir().unwrap();
+    fs::create_dir(dir.path().join("Documents")).unwrap();
+    fs::write(dir.path().join("Documents/hello.txt"), "Hello, world!").unwrap();
+    fs::write(dir.path().join("Documents/readme.md"), "different").unwrap();
+
+    let output = Command::new(fiq_bin())
+        .args(["organize", dir.path().to_str().unwrap(), "--mode", "dedupe"])
+        .env("FIQ_STATE_DIR", state.path())
+        .output()
+        .expect("failed to run fiq organize --mode dedupe");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert!(stdout.contains("Identical to destination"));
+    // Identical source dropped, different one kept under a new name
+    assert!(!dir.path().join("hello.txt").exists());
+    assert!(dir.path().join("Documents/readme_1.md").exists());
+
+    // Undo brings the dropped duplicate back
+    let output = Command::new(fiq_bin())
+        .arg("undo")
+        .env("FIQ_STATE_DIR", state.path())
+        .output()
+        .expect("failed to run fiq undo");
+    assert!(output.status.success());
+    assert_eq!(
+        fs::read_to_string(dir.path().join("hello.txt")).unwrap(),
+        "Hello, world!"
+    );
+    assert!(dir.path().join("Documents/hello.txt").exists());
+}
+
+#[test]
+fn test_organize_skips_own_folders_and_prunes() {
+    let dir = create_test_dir();
+    let state = tempfile::tempdir().unwrap();
+    let sorted = dir.path().join("sorted");
+
+    let organize = |extra: &[&str]| {
+        let output = Command::new(fiq_bin())
+            .args(["organize", dir.path().to_str().unwrap()])
+            .args(extra)
+            .env("FIQ_STATE_DIR", state.path())
+            .output()
+            .expect("failed to run fiq organize");
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stdout).to_string()
+    };
+
+    // Output nested in the source: a second copy run must not pick up
+    // the first run's results
+    let copy = [
+        "--action",
+        "copy",
+        "--mode",
+        "dedupe",
+        "--output",
+        sorted.to_str().unwrap(),
+    ];
+    organize(&copy);
+    let stdout = organize(&copy);
+    assert!(stdout.contains("Identical to destination"));
+    assert!(!stdout.contains("→"));
+    assert!(!sorted.join("Documents/hello_1.txt").exists());
+
+    // In place: files already in category folders stay put, and emptied
+    // folders are removed
+    fs::remove_dir_all(&sorted).unwrap();
+    let stdout = organize(&[
+        "--template",
+        "{category}/{stem}-{hash:4}.{ext}",
+        "--prune-empty",
+    ]);
+    assert!(stdout.contains("Removed empty folders"));
+    assert!(!dir.path().join("subdir").exists());
+    let stdout = organize(&[
+        "--template",
+        "{category}/{stem}-{hash:4}.{ext}",
+        "--dry-run",
+    ]);
+    assert!(!stdout.contains("→"));
+}
+
+#[test]
+fn test_organize_preflight_and_rollback() {
+    let dir = create_test_dir();
+    let out = tempfile::tempdir().unwrap();
+    let state = tempfile::tempdir().unwrap();
+    let organize = |extra: &[&str]| {
+        let output = Command::new(fiq_bin())
+            .args(["organize", dir.path().to_str().unwrap()])
+            .args(extra)
+            .env("FIQ_STATE_DIR", state.path())
+            .output()
+            .expect("failed to run fiq organize");
+        String::from_utf8_lossy(&output.stdout).to_string()
+    };
+
+    // A file where the Code folder should go fails preflight; nothing moves
+    fs::write(out.path().join("Code"), "in the way").unwrap();
+    let output = ["--output", out.path().to_str().unwrap()];
+    let stdout = organize(&output);
+    assert!(stdout.contains("nothing was changed"));
+    assert!(dir.path().join("hello.txt").exists());
+    assert!(!out.path().join("Documents").exists());
+
+    // --keep-going moves everything else
+    let stdout = organize(&[&output[..], &["--keep-going"]].concat());
+    assert!(stdout.contains("is not a directory"));
+    assert!(out.path().join("Documents/hello.txt").exists());
+    assert!(dir.path().join("main.rs").exists());
+
+    // A failure mid-run (a name too long for the file system) reverses the
+    // moves already made
+    fs::write(dir.path().join(format!("{}.txt", "x".repeat(100))), "long").unwrap();
+    let stdout = organize(&["--template", "{stem}{stem}{stem}.{ext}"]);
+    assert!(stdout.contains("rolled back"));
+    assert!(dir.path().join("main.rs").exists());
+    assert!(!dir.path().join("mainmainmain.rs").exists());
+}
+
+#[test]
+fn test_organize_leaves_partial_downloads() {
+    let dir = create_test_dir();
+    let state = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("movie.mp4.part"), "half").unwrap();
+    fs::write(dir.path().join("report.pdf.crdownload"), "half").unwrap();
+
+    let output = Command::new(fiq_bin())
+        .args(["organize", dir.path().to_str().unwrap()])
+        .env("FIQ_STATE_DIR", state.path())
+        .output()
+        .expect("failed to run fiq organize");
+    assert!(output.status.success());
+    assert!(dir.path().join("movie.mp4.part").exists());
+    assert!(dir.path().join("report.pdf.crdownload").exists());
+    assert!(dir.path().join("Documents/hello.txt").exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_organize_watch() {
+    let dir = tempfile::tempdir().unwrap();
+    let state = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("early.txt"), "early").unwrap();
+
+    let mut child = Command::new(fiq_bin())
+        .args(["organize", dir.path().to_str().unwrap(), "--watch"])
+        .args(["--settle", "1"])
+        .env("FIQ_STATE_DIR", state.path())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .expect("failed to run fiq organize --watch");
+
+    let wait_for = |path: &std::path::Path| {
+        (0..100).any(|_| {
+            std::thread::sleep(std::time::Duration::from_millis(100));
+            path.exists()
+        })
+    };
+    // Existing files are organized at startup, new ones once they settle
+    assert!(wait_for(&dir.path().join("Documents/early.txt")));
+    fs::write(dir.path().join("late.rs"), "fn main() {}").unwrap();
+    assert!(wait_for(&dir.path().join("Code/late.rs")));
+
+    let killed = Command::new("kill")
+        .args(["-TERM", &child.id().to_string()])
+        .status()
+        .unwrap();
+    assert!(killed.success());
+    assert!(child.wait().unwrap().success());
+}
+
+#[test]
+fn test_rename() {
+    let dir = tempfile::tempdir().unwrap();
+    let state = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("a_b.txt"), "ab").unwrap();
+    fs::write(dir.path().join("b_a.txt"), "ba").unwrap();
+    fs::write(dir.path().join("IMG_7.jpg"), "img").unwrap();
+    let rename = |args: &[&str]| {
+        Command::new(fiq_bin())
+            .args(["rename", dir.path().to_str().unwrap()])
+            .args(args)
+            .env("FIQ_STATE_DIR", state.path())
+            .output()
+            .expect("failed to run fiq rename")
+    };
+
+    // Preview by default
+    let output = rename(&["--match", r"^IMG_(\d+)", "--to", "photo-{n:3}.{ext:upper}"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("photo-001.JPG"));
+    assert!(dir.path().join("IMG_7.jpg").exists());
+
+    // Two files onto one name is refused as a whole
+    let output = rename(&["--match", r"\.txt$", "--to", "same.txt", "--apply"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("would both be renamed"));
+    assert!(dir.path().join("a_b.txt").exists());
+
+    // A swap goes through a temporary name
+    let swap = [
+        "--match",
+        r"^(\w)_(\w)\.txt$",
+        "--to",
+        "{2}_{1}.txt",
+        "--apply",
+    ];
+    assert!(rename(&swap).status.success());
+    assert_eq!(
+        fs::read_to_string(dir.path().join("a_b.txt")).unwrap(),
+        "ba"
+    );
+    assert_eq!(
+        fs::read_to_string(dir.path().join("b_a.txt")).unwrap(),
+        "ab"
+    );
+
+    let output = Command::new(fiq_bin())
+        .arg("undo")
+        .env("FIQ_STATE_DIR", state.path())
+        .output()
+        .expect("failed to run fiq undo");
+    assert!(output.status.success());
+    assert_eq!(
+        fs::read_to_string(dir.path().join("a_b.txt")).unwrap(),
+        "ab"
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_stats_disk_usage_and_sparse_files() {
+    let dir = tempfile::tempdir().unwrap();
+    // 50 MB long, nothing allocated
+    fs::File::create(dir.path().join("disk.img"))
+        .unwrap()
+        .set_len(50_000_000)
+        .unwrap();
+
+    let stats = |extra: &[&str]| {
+        let output = Command::new(fiq_bin())
+            .args(["stats", dir.path().to_str().unwrap()])
+            .args(extra)
+            .output()
+            .expect("failed to run fiq stats");
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stdout).to_string()
+    };
+
+    let apparent = stats(&[]);
+    assert!(apparent.contains("50.00 MB"));
+    assert!(apparent.contains("[sparse]"));
+
+    let disk = stats(&["--disk-usage"]);
+    assert!(disk.contains("Disk usage"));
+    assert!(!disk.contains("50.00 MB"));
+}
+
+#[test]
+fn test_stats_histograms() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("new.txt"), "fresh").unwrap();
+    fs::write(dir.path().join("old.log"), vec![b'x'; 5_000]).unwrap();
+    fs::File::options()
+        .write(true)
+        .open(dir.path().join("old.log"))
+        .unwrap()
+        .set_modified(
+            std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_500_000_000),
+        )
+        .unwrap();
+
+    let output = Command::new(fiq_bin())
+        .args(["stats", dir.path().to_str().unwrap(), "--histograms"])
+        .output()
+        .expect("failed to run fiq stats --histograms");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert!(stdout.contains("Files by Size"));
+    // Match from the end: a section's first row follows a color reset,
+    // and extensions also appear in the By Extension table
+    let row = |label: &str| {
+        stdout
+            .lines()
+            .rfind(|l| l.trim_start().starts_with(label))
+            .unwrap_or_else(|| panic!("no {} row in {}", label, stdout))
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    assert!(row("1-10 KB").starts_with("1-10 KB 1 5.00 KB #"));
+    assert!(row("older").starts_with("older 1 5.00 KB #"));
+    assert!(row(".log").ends_with("0% 0% 0% 0% 100%"));
+    assert!(row(".txt").starts_with(".txt 100% 0%"));
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_stats_snapshot_compare() {
+    let dir = tempfile::tempdir().unwrap();
+    let cache = tempfile::tempdir().unwrap();
+    fs::create_dir(dir.path().join("media")).unwrap();
+    fs::write(dir.path().join("media/old.iso"), vec![0u8; 2_000_000]).unwrap();
+
+    let stats = |extra: &[&str]| {
+        let output = Command::new(fiq_bin())
+            .args(["stats", dir.path().to_str().unwrap()])
+            .args(extra)
+            .env("XDG_CACHE_HOME", cache.path())
+            .output()
+            .expect("failed to run fiq stats");
+        (
+            output.status.success(),
+            String::from_utf8_lossy(&output.stdout).to_string(),
+        )
+    };
+
+    let (ok, stdout) = stats(&["--compare", "last"]);
+    assert!(!ok);
+    assert!(stdout.contains("No snapshots of"));
+
+    let (ok, stdout) = stats(&["--save-snapshot"]);
+    assert!(ok);
+    assert!(stdout.contains("Saved snapshot:"));
+
+    fs::remove_file(dir.path().join("media/old.iso")).unwrap();
+    fs::write(dir.path().join("media/new.mkv"), vec![0u8; 3_000_000]).unwrap();
+    let (ok, stdout) = stats(&["--compare", "last"]);
+    assert!(ok);
+    assert!(stdout.contains("Growth by Directory"));
+    assert!(stdout.contains("+1.00 MB"));
+    let new_files = stdout.split("New Large Files").nth(1).unwrap();
+    let (new_files, deleted) = new_files.split_once("Deleted Large Files").unwrap();
+    assert!(new_files.contains("new.mkv"));
+    assert!(deleted.contains("old.iso"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_stats_owner_and_audit() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempfile::tempdir().unwrap();
+    let shared = dir.path().join("shared.txt");
+    fs::write(&shared, "anyone").unwrap();
+    fs::set_permissions(&shared, fs::Permissions::from_mode(0o666)).unwrap();
+    let tool = dir.path().join("tool");
+    fs::write(&tool, "#!/bin/sh").unwrap();
+    fs::set_permissions(&tool, fs::Permissions::from_mode(0o4755)).unwrap();
+    fs::write(dir.path().join("plain.txt"), "fine").unwrap();
+
+    let output = Command::new(fiq_bin())
+        .args([
+            "stats",
+            dir.path().to_str().unwrap(),
+            "--by",
+            "owner",
+            "--audit",
+        ])
+        .output()
+        .expect("failed to run fiq stats --audit");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert!(stdout.contains("By Owner"));
+    let audit = stdout.split("Permission Audit").nth(1).unwrap();
+    assert!(audit.contains("0666"));
+    assert!(audit.contains("shared.txt"));
+    assert!(audit.contains("4755"));
+    assert!(!audit.contains("plain.txt"));
+
+    // Lists are capped at --top, counts are not
+    let notes = dir.path().join("notes.txt");
+    fs::write(&notes, "also anyone's").unwrap();
+    fs::set_permissions(&notes, fs::Permissions::from_mode(0o666)).unwrap();
+    let output = Command::new(fiq_bin())
+        .args([
+            "stats",
+            dir.path().to_str().unwrap(),
+            "--audit",
+            "--top",
+            "1",
+        ])
+        .output()
+        .expect("failed to run fiq stats --audit");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let audit = stdout.split("Permission Audit").nth(1).unwrap();
+    let writable = audit.split("Setuid").next().unwrap();
+    assert!(writable.contains("notes.txt"));
+    assert!(!writable.contains("shared.txt"));
+    assert!(writable.contains("... and 1 more"));
+
+    let output = Command::new(fiq_bin())
+        .args(["stats", dir.path().to_str().unwrap(), "--by", "size"])
+        .output()
+        .expect("failed to run fiq stats --by");
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_stats_code_lines() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(
+        dir.path().join("main.rs"),
+        "// entry point\n\nfn main() {\n    println!(\"hi\");\n}\n",
+    )
+    .unwrap();
+    fs::write(dir.path().join("tool.py"), "# helper\nprint(1)\n").unwrap();
+    fs::write(dir.path().join("notes.md"), "# not code\n").unwrap();
+
+    let output = Command::new(fiq_bin())
+        .args(["stats", dir.path().to_str().unwrap(), "--code"])
+        .output()
+        .expect("failed to run fiq stats --code");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    let row = |name: &str| {
+        stdout
+            .lines()
+            .find(|l| l.trim_start().starts_with(name))
+            .map(|l| l.split_whitespace().collect::<Vec<_>>().join(" "))
+    };
+    assert_eq!(row("Rust").as_deref(), Some("Rust 1 3 1 1"));
+    assert_eq!(row("Python").as_deref(), Some("Python 1 1 1 0"));
+    assert_eq!(row("Total").as_deref(), Some("Total 2 4 2 1"));
+}
+
+#[test]
+fn test_cold_ranks_old_large_files() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::create_dir(dir.path().join("archive")).unwrap();
+    let age = |path: &std::path::Path, days: u64| {
+        let when = std::time::SystemTime::now() - std::time::Duration::from_secs(days * 86_400);
+        fs::File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_times(fs::FileTimes::new().set_modified(when).set_accessed(when))
+            .unwrap();
+    };
+    let older = dir.path().join("archive/older.iso");
+    fs::write(&older, vec![0u8; 2_000_000]).unwrap();
+    age(&older, 1000);
+    let bigger = dir.path().join("bigger.img");
+    fs::write(&bigger, vec![0u8; 5_000_000]).unwrap();
+    age(&bigger, 500);
+    let small = dir.path().join("small.txt");
+    fs::write(&small, "old but small").unwrap();
+    age(&small, 1000);
+    fs::write(dir.path().join("fresh.bin"), vec![0u8; 2_000_000]).unwrap();
+
+    let output = Command::new(fiq_bin())
+        .args([
+            "cold",
+            dir.path().to_str().unwrap(),
+            "--older",
+            "180d",
+            "--min-size",
+            "1MB",
+        ])
+        .output()
+        .expect("failed to run fiq cold");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert!(stdout.contains("7.00 MB"));
+    // 5 MB × 500 days outranks 2 MB × 1000 days
+    let ranked = stdout.split("Coldest Files").nth(1).unwrap();
+    let bigger_at = ranked.find("bigger.img").unwrap();
+    let older_at = ranked.find("older.iso").unwrap();
+    assert!(bigger_at < older_at);
+    assert!(!stdout.contains("small.txt"));
+    assert!(!stdout.contains("fresh.bin"));
+    assert!(stdout.contains("archive"));
+
+    let output = Command::new(fiq_bin())
+        .args(["cold", dir.path().to_str().unwrap(), "--older", "soon"])
+        .output()
+        .expect("failed to run fiq cold");
+    assert!(!output.status.success());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_clean_report_trash_and_undo() {
+    let dir = tempfile::tempdir().unwrap();
+    let state = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    fs::write(root.join("empty.txt"), "").unwrap();
+    fs::write(root.join("kept.txt"), "content").unwrap();
+    fs::create_dir_all(root.join("hollow/inner")).unwrap();
+    std::os::unix::fs::symlink(root.join("gone"), root.join("dangling")).unwrap();
+    fs::write(root.join(".DS_Store"), "finder").unwrap();
+    fs::create_dir_all(root.join("pkg/__pycache__")).unwrap();
+    fs::write(root.join("pkg/__pycache__/mod.pyc"), "bytecode").unwrap();
+    fs::write(root.join("pkg/__init__.py"), "").unwrap();
+    fs::create_dir_all(root.join(".git/refs/tags")).unwrap();
+    let mkfifo = Command::new("mkfifo").arg(root.join("pipe")).status();
+    assert!(mkfifo.is_ok_and(|s| s.success()));
+
+    let output = Command::new(fiq_bin())
+        .args(["clean", root.to_str().unwrap(), "-r"])
+        .env("FIQ_STATE_DIR", state.path())
+        .output()
+        .expect("failed to run fiq clean");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert!(stdout.contains("empty.txt"));
+    assert!(stdout.contains("hollow"));
+    // Only the topmost empty directory is listed
+    assert!(!stdout.contains("inner"));
+    assert!(stdout.contains("dangling"));
+    assert!(stdout.contains(".DS_Store"));
+    assert!(stdout.contains("__pycache__"));
+    assert!(!stdout.contains("mod.pyc"));
+    assert!(!stdout.contains("__init__.py"));
+    assert!(!stdout.contains("refs"));
+    assert!(!stdout.contains("kept.txt"));
+    assert!(!stdout.contains("pipe"));
+    assert!(root.join("empty.txt").exists());
+
+    let output = Command::new(fiq_bin())
+        .args(["clean", root.to_str().unwrap(), "-r", "--trash"])
+        .env("FIQ_STATE_DIR", state.path())
+        .output()
+        .expect("failed to run fiq clean --trash");
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("fiq undo"));
+    assert!(!root.join("empty.txt").exists());
+    assert!(!root.join("hollow").exists());
+    assert!(root.join("dangling").symlink_metadata().is_err());
+    assert!(!root.join("pkg/__pycache__").exists());
+    assert!(root.join("pkg/__init__.py").exists());
+    assert!(root.join(".git/refs/tags").exists());
+    assert!(root.join("pipe").exists());
+
+    let output = Command::new(fiq_bin())
+        .arg("undo")
+        .env("FIQ_STATE_DIR", state.path())
+        .output()
+        .expect("failed to run fiq undo");
+    assert!(output.status.success());
+    assert!(root.join("empty.txt").exists());
+    assert!(root.join("hollow/inner").is_dir());
+    assert!(root.join("dangling").symlink_metadata().is_ok());
+    assert!(root.join("pkg/__pycache__/mod.pyc").exists());
+    assert!(root.join(".DS_Store").exists());
+}
+
 #[test]
 fn test_no_command_exits_with_error() {
     let output = Command::new(fiq_bin()).output().expect("failed to run fiq");
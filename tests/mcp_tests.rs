@@ -108,10 +108,15 @@ fn test_mcp_tools_list() {
         .collect();
 
     assert!(tool_names.contains(&"scan_stats"));
+    assert!(tool_names.contains(&"stats_diff"));
     assert!(tool_names.contains(&"find_duplicates"));
     assert!(tool_names.contains(&"search_files"));
     assert!(tool_names.contains(&"organize_files"));
+    assert!(tool_names.contains(&"rename_files"));
     assert!(tool_names.contains(&"build_index"));
+    assert!(tool_names.contains(&"compare_directories"));
+    assert!(tool_names.contains(&"find_by_hash"));
+    assert!(tool_names.contains(&"undo_organize"));
 }
 
 #[test]
@@ -136,6 +141,110 @@ fn test_mcp_scan_stats() {
     assert!(stats["total_size"].is_number());
 }
 
+#[test]
+fn test_mcp_scan_stats_tree() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(dir.path().join("big/deep")).unwrap();
+    std::fs::create_dir(dir.path().join("small")).unwrap();
+    std::fs::write(dir.path().join("big/deep/a.bin"), vec![0u8; 3000]).unwrap();
+    std::fs::write(dir.path().join("big/b.bin"), vec![0u8; 1000]).unwrap();
+    std::fs::write(dir.path().join("small/c.bin"), vec![0u8; 10]).unwrap();
+    let request = format!(
+        r#"{{"jsonrpc":"2.0","id":10,"method":"tools/call","params":{{"name":"scan_stats","arguments":{{"directory":"{}","tree":true,"depth":1}}}}}}"#,
+        dir.path().display().to_string().replace('\\', "\\\\")
+    );
+
+    let response = send_mcp_request(&request);
+    let parsed: serde_json::Value = serde_json::from_str(response.trim()).expect("invalid JSON");
+    let text = parsed["result"]["content"][0]["text"]
+        .as_str()
+        .expect("missing text");
+    let stats: serde_json::Value = serde_json::from_str(text).expect("invalid stats JSON");
+
+    let tree = &stats["tree"];
+    assert_eq!(tree["size"], 4010);
+    assert_eq!(tree["files"], 3);
+    // Largest first, cumulative, and cut off below depth 1
+    let big = &tree["children"][0];
+    assert!(big["path"].as_str().unwrap().ends_with("big"));
+    assert_eq!(big["size"], 4000);
+    assert!(big.get("children").is_none());
+    assert!(
+        stats["largest_dirs"][0]["path"]
+            .as_str()
+            .unwrap()
+            .ends_with("deep")
+    );
+}
+
+#[test]
+fn test_mcp_stats_diff_without_snapshot() {
+    let dir = tempfile::tempdir().unwrap();
+    let request = format!(
+        r#"{{"jsonrpc":"2.0","id":11,"method":"tools/call","params":{{"name":"stats_diff","arguments":{{"directory":"{}"}}}}}}"#,
+        dir.path().display().to_string().replace('\\', "\\\\")
+    );
+
+    let response = send_mcp_request(&request);
+    let parsed: serde_json::Value = serde_json::from_str(response.trim()).expect("invalid JSON");
+    assert_eq!(parsed["result"]["isError"], true);
+    let text = parsed["result"]["content"][0]["text"]
+        .as_str()
+        .expect("missing text");
+    assert!(text.contains("No snapshots of"));
+}
+
+#[test]
+fn test_mcp_compare_directories() {
+    let left = tempfile::tempdir().unwrap();
+    let right = tempfile::tempdir().unwrap();
+    std::fs::write(left.path().join("a.txt"), "same").unwrap();
+    std::fs::write(right.path().join("a.txt"), "same").unwrap();
+    std::fs::write(left.path().join("b.txt"), "left only").unwrap();
+
+    let request = format!(
+        r#"{{"jsonrpc":"2.0","id":8,"method":"tools/call","params":{{"name":"compare_directories","arguments":{{"left":"{}","right":"{}","verify":true}}}}}}"#,
+        left.path().display().to_string().replace('\\', "\\\\"),
+        right.path().display().to_string().replace('\\', "\\\\")
+    );
+
+    let response = send_mcp_request(&request);
+    let parsed: serde_json::Value = serde_json::from_str(response.trim()).expect("invalid JSON");
+    let text = parsed["result"]["content"][0]["text"]
+        .as_str()
+        .expect("missing text");
+    let diff: serde_json::Value = serde_json::from_str(text).expect("invalid diff JSON");
+    assert_eq!(diff["same"], 1);
+    assert_eq!(diff["only_left"][0]["path"], "b.txt");
+    assert!(diff["only_right"].as_array().unwrap().is_empty());
+}
+
+#[test]
+fn test_mcp_find_by_hash() {
+    let dir = tempfile::tempdir().unwrap();
+    let needle = tempfile::NamedTempFile::new().unwrap();
+    std::fs::write(needle.path(), "needle").unwrap();
+    std::fs::write(dir.path().join("a.txt"), "needle").unwrap();
+    std::fs::write(dir.path().join("b.txt"), "haystack").unwrap();
+
+    let request = format!(
+        r#"{{"jsonrpc":"2.0","id":9,"method":"tools/call","params":{{"name":"find_by_hash","arguments":{{"file":"{}","directories":["{}"],"use_cache":false}}}}}}"#,
+        needle.path().display().to_string().replace('\\', "\\\\"),
+        dir.path().display().to_string().replace('\\', "\\\\")
+    );
+
+    let response = send_mcp_request(&request);
+    let parsed: serde_json::Value = serde_json::from_str(response.trim()).expect("invalid JSON");
+    let text = parsed["result"]["content"][0]["text"]
+        .as_str()
+        .expect("missing text");
+    let found: serde_json::Value = serde_json::from_str(text).expect("invalid JSON");
+    assert_eq!(found["hash"].as_str().unwrap().len(), 64);
+    let matches = found["matches"].as_array().unwrap();
+    assert_eq!(matches.len(), 1);
+    assert!(matches[0]["path"].as_str().unwrap().ends_with("a.txt"));
+}
+
 #[test]
 fn test_mcp_notification_no_response() {
     let responses = send_mcp_requests(&[